@@ -26,6 +26,6 @@ use bootstrap::{Flags, Config, Build};
 fn main() {
     let args = env::args().skip(1).collect::<Vec<_>>();
     let flags = Flags::parse(&args);
-    let config = Config::parse(&flags.build, flags.config.clone());
+    let config = Config::parse(&flags.build, flags.config.clone(), flags.profile.clone());
     Build::new(flags, config).build();
 }
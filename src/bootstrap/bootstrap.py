@@ -168,8 +168,7 @@ def format_build_time(duration):
 class RustBuild(object):
 
     def download_stage0(self):
-        cache_dst = os.path.join(self.build_dir, "cache")
-        rustc_cache = os.path.join(cache_dst, self.stage0_date())
+        rustc_cache = os.path.join(self.build_dir, "cache", self.stage0_date())
         if not os.path.exists(rustc_cache):
             os.makedirs(rustc_cache)
 
@@ -183,23 +182,11 @@ class RustBuild(object):
                 shutil.rmtree(self.bin_root())
             filename = "rust-std-{}-{}.tar.gz".format(
                 rustc_channel, self.build)
-            url = self._download_url + "/dist/" + self.stage0_date()
-            tarball = os.path.join(rustc_cache, filename)
-            if not os.path.exists(tarball):
-                get("{}/{}".format(url, filename),
-                    tarball, verbose=self.verbose)
-            unpack(tarball, self.bin_root(),
-                   match="rust-std-" + self.build,
-                   verbose=self.verbose)
+            pattern = "rust-std-" + self.build
+            self._download_stage0_helper(filename, pattern)
 
             filename = "rustc-{}-{}.tar.gz".format(rustc_channel, self.build)
-            url = self._download_url + "/dist/" + self.stage0_date()
-            tarball = os.path.join(rustc_cache, filename)
-            if not os.path.exists(tarball):
-                get("{}/{}".format(url, filename),
-                    tarball, verbose=self.verbose)
-            unpack(tarball, self.bin_root(),
-                   match="rustc", verbose=self.verbose)
+            self._download_stage0_helper(filename, "rustc")
             self.fix_executable(self.bin_root() + "/bin/rustc")
             self.fix_executable(self.bin_root() + "/bin/rustdoc")
             with open(self.rustc_stamp(), 'w') as f:
@@ -208,29 +195,57 @@ class RustBuild(object):
             if "pc-windows-gnu" in self.build:
                 filename = "rust-mingw-{}-{}.tar.gz".format(
                     rustc_channel, self.build)
-                url = self._download_url + "/dist/" + self.stage0_date()
-                tarball = os.path.join(rustc_cache, filename)
-                if not os.path.exists(tarball):
-                    get("{}/{}".format(url, filename),
-                        tarball, verbose=self.verbose)
-                unpack(tarball, self.bin_root(),
-                       match="rust-mingw", verbose=self.verbose)
+                self._download_stage0_helper(filename, "rust-mingw")
 
         if self.cargo().startswith(self.bin_root()) and \
                 (not os.path.exists(self.cargo()) or self.cargo_out_of_date()):
             self.print_what_it_means_to_bootstrap()
             filename = "cargo-{}-{}.tar.gz".format(cargo_channel, self.build)
-            url = self._download_url + "/dist/" + self.stage0_date()
-            tarball = os.path.join(rustc_cache, filename)
-            if not os.path.exists(tarball):
-                get("{}/{}".format(url, filename),
-                    tarball, verbose=self.verbose)
-            unpack(tarball, self.bin_root(),
-                   match="cargo", verbose=self.verbose)
+            self._download_stage0_helper(filename, "cargo")
             self.fix_executable(self.bin_root() + "/bin/cargo")
             with open(self.cargo_stamp(), 'w') as f:
                 f.write(self.stage0_date())
 
+    def _download_stage0_helper(self, filename, pattern):
+        url = self._download_url + "/dist/" + self.stage0_date()
+        tarball = os.path.join(self.build_dir, "cache", self.stage0_date(),
+                               filename)
+        if not os.path.exists(tarball):
+            get("{}/{}".format(url, filename), tarball, verbose=self.verbose)
+        if self.hermetic:
+            self.verify_hermetic(tarball,
+                                 self.stage0_date() + "/" + filename)
+        unpack(tarball, self.bin_root(), match=pattern, verbose=self.verbose)
+
+    def verify_hermetic(self, path, name):
+        """Check the sha256 of `path` against the `sha256 <name> <hash>`
+        line of the hermetic lockfile, before anything is unpacked from it.
+        The tools are checked once the Rust part of bootstrap runs."""
+        lockfile = self.get_toml('hermetic-lock') or \
+            os.path.join(self.rust_root, "hermetic.lock")
+        if self.verbose:
+            print("verifying " + path + " against " + lockfile)
+        with open(path, "rb") as source:
+            found = hashlib.sha256(source.read()).hexdigest()
+        expected = None
+        try:
+            with open(lockfile) as f:
+                for line in f:
+                    parts = line.split()
+                    if len(parts) == 3 and parts[:2] == ["sha256", name]:
+                        expected = parts[2]
+        except IOError:
+            raise RuntimeError("hermetic lockfile {} does not exist"
+                               .format(lockfile))
+        if expected is None:
+            raise RuntimeError("downloaded file {} is not in the hermetic "
+                               "lockfile {}, add `sha256 {} {}` to use it"
+                               .format(name, lockfile, name, found))
+        if found != expected:
+            raise RuntimeError("{} has sha256 {}, but {} is required by the "
+                               "hermetic lockfile {}"
+                               .format(name, found, expected, lockfile))
+
     def fix_executable(self, fname):
         # If we're on NixOS we need to change the path to the dynamic loader
 
@@ -340,6 +355,9 @@ class RustBuild(object):
         return None
 
     def cargo(self):
+        if self.installed_rustc:
+            return os.path.join(self.installed_rustc,
+                                "bin/cargo" + self.exe_suffix())
         config = self.get_toml('cargo')
         if config:
             return config
@@ -349,6 +367,9 @@ class RustBuild(object):
         return os.path.join(self.bin_root(), "bin/cargo" + self.exe_suffix())
 
     def rustc(self):
+        if self.installed_rustc:
+            return os.path.join(self.installed_rustc,
+                                "bin/rustc" + self.exe_suffix())
         config = self.get_toml('rustc')
         if config:
             return config
@@ -563,10 +584,29 @@ class RustBuild(object):
         return "{}-{}".format(cputype, ostype)
 
     def update_submodules(self):
+        """Check out the submodules the build needs.
+
+        `build.submodules` selects how: `true` (the default) fetches their
+        full history, `"shallow"` only the commits which are checked out and
+        `"mirror:<path>"` borrows the objects from a local mirror, a directory
+        laid out like `.git/modules` (e.g. `<path>/src/llvm`), falling back to
+        a shallow fetch for submodules missing from it.
+        """
+        mode = self.get_toml('submodules')
         if (not os.path.exists(os.path.join(self.rust_root, ".git"))) or \
-                self.get_toml('submodules') == "false" or \
+                mode == "false" or \
                 self.get_mk('CFG_DISABLE_MANAGE_SUBMODULES') == "1":
             return
+        mirror = None
+        if mode is not None and mode.startswith("mirror:"):
+            mirror = os.path.expanduser(mode[len("mirror:"):])
+            mirror = os.path.abspath(mirror)
+            if not os.path.isdir(mirror):
+                raise Exception("submodule mirror {} does not exist"
+                                .format(mirror))
+        elif mode not in (None, "true", "shallow"):
+            raise Exception("invalid value for build.submodules: {}"
+                            .format(mode))
         print('Updating submodules')
         default_encoding = sys.getdefaultencoding()
         run(["git", "submodule", "-q", "sync"], cwd=self.rust_root)
@@ -582,9 +622,20 @@ class RustBuild(object):
                               (module.endswith("jemalloc") and
                                (self.get_toml('jemalloc') or
                                 self.get_mk('CFG_JEMALLOC_ROOT'))))]
-        run(["git", "submodule", "update",
-             "--init", "--recursive"] + submodules,
-            cwd=self.rust_root, verbose=self.verbose)
+        update = ["git", "submodule", "update", "--init", "--recursive"]
+        if mirror is None:
+            if mode == "shallow":
+                update += ["--depth", "1"]
+            run(update + submodules, cwd=self.rust_root, verbose=self.verbose)
+        else:
+            for module in submodules:
+                reference = os.path.join(mirror, module)
+                if os.path.isdir(reference):
+                    args = ["--reference", reference]
+                else:
+                    args = ["--depth", "1"]
+                run(update + args + [module],
+                    cwd=self.rust_root, verbose=self.verbose)
         run(["git", "submodule", "-q", "foreach", "git",
              "reset", "-q", "--hard"],
             cwd=self.rust_root, verbose=self.verbose)
@@ -598,6 +649,7 @@ def bootstrap():
     parser.add_argument('--config')
     parser.add_argument('--clean', action='store_true')
     parser.add_argument('-v', '--verbose', action='store_true')
+    parser.add_argument('--with-installed-rustc')
 
     args = [a for a in sys.argv if a != '-h' and a != '--help']
     args, _ = parser.parse_known_args(args)
@@ -610,6 +662,9 @@ def bootstrap():
     rb.build_dir = os.path.join(os.getcwd(), "build")
     rb.verbose = args.verbose
     rb.clean = args.clean
+    rb.installed_rustc = None
+    if args.with_installed_rustc:
+        rb.installed_rustc = os.path.abspath(args.with_installed_rustc)
 
     try:
         with open(args.config or 'config.toml') as config:
@@ -632,6 +687,9 @@ def bootstrap():
     rb.use_locked_deps = '\nlocked-deps = true' in rb.config_toml or \
                          'CFG_ENABLE_LOCKED_DEPS' in rb.config_mk
 
+    rb.hermetic = '\nhermetic = true' in rb.config_toml or \
+                  '--hermetic' in sys.argv
+
     if 'SUDO_USER' in os.environ and not rb.use_vendored_sources:
         if os.environ.get('USER') != os.environ['SUDO_USER']:
             rb.use_vendored_sources = True
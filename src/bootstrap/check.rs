@@ -14,15 +14,20 @@
 //! our CI.
 
 use std::collections::HashSet;
+use std::collections::hash_map::DefaultHasher;
 use std::env;
 use std::iter;
 use std::fmt;
 use std::fs::{self, File};
+use std::hash::{Hash, Hasher};
+use std::mem;
 use std::path::{PathBuf, Path};
-use std::process::Command;
-use std::io::Read;
+use std::process::{Command, Stdio};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::sync::mpsc;
+use std::thread;
 
-use build_helper::{self, output};
+use build_helper::{self, output, mtime};
 
 use {Build, Compiler, Mode};
 use dist;
@@ -58,33 +63,296 @@ impl fmt::Display for TestKind {
     }
 }
 
-fn try_run(build: &Build, cmd: &mut Command) {
-    if !build.fail_fast {
-        if !build.try_run(cmd) {
-            let failures = build.delayed_failures.get();
-            build.delayed_failures.set(failures + 1);
+fn try_run(build: &Build, cmd: &mut Command) -> bool {
+    retry(build, cmd, &|cmd| build.try_run(cmd), &|cmd| build.run(cmd))
+}
+
+/// Runs `cmd` with `try_run`, rerunning it up to as many times as requested
+/// with `--retry` while it fails.
+///
+/// A command which only succeeds when retried is counted as flaky. A command
+/// which fails every time is fatal, unless `--no-fail-fast` was passed in
+/// which case the failure is postponed like for a command run only once.
+fn retry(build: &Build,
+         cmd: &mut Command,
+         try_run: &Fn(&mut Command) -> bool,
+         run: &Fn(&mut Command)) -> bool {
+    let retries = build.flags.cmd.retry();
+    for attempt in 0..retries {
+        if try_run(cmd) {
+            if attempt > 0 {
+                build.flaky_runs.set(build.flaky_runs.get() + 1);
+            }
+            return true
         }
+        println!("\ncommand did not execute successfully, retrying ({}/{})\n",
+                 attempt + 1, retries);
+    }
+
+    let success = if build.fail_fast {
+        run(cmd);
+        true
     } else {
-        build.run(cmd);
+        try_run(cmd)
+    };
+    if !success {
+        build.delayed_failures.set(build.delayed_failures.get() + 1);
+    } else if retries > 0 {
+        build.flaky_runs.set(build.flaky_runs.get() + 1);
     }
+    success
 }
 
-fn try_run_quiet(build: &Build, cmd: &mut Command) {
-    if !build.fail_fast {
-        if !build.try_run_quiet(cmd) {
-            let failures = build.delayed_failures.get();
-            build.delayed_failures.set(failures + 1);
+/// Runs `cmd` like `try_run`, unless a previous successful run with the same
+/// inputs is recorded in `stamp`.
+///
+/// The inputs are the command line itself (which captures the compiler and
+/// test arguments), the configuration and `RUSTFLAGS`-like environment
+/// variables its environment is made of, and the modification times of every
+/// file underneath `inputs`. On success the hash of these is written to
+/// `stamp`, so the next invocation can skip the test suite if nothing changed.
+/// Passing `--force-rerun` always runs the tests.
+fn try_run_cached(build: &Build, cmd: &mut Command, stamp: &Path, inputs: &[PathBuf]) {
+    if let Some(hash) = inputs_hash(build, cmd, stamp, inputs) {
+        if try_run(build, cmd) {
+            write_stamp(stamp, &hash);
+        }
+    }
+}
+
+/// Returns the hash of the inputs of `cmd`, as described for `try_run_cached`,
+/// or `None` if it's recorded in `stamp` and `cmd` needn't be run.
+fn inputs_hash(build: &Build,
+               cmd: &Command,
+               stamp: &Path,
+               inputs: &[PathBuf]) -> Option<String> {
+    let mut hasher = DefaultHasher::new();
+    format!("{:?}", cmd).hash(&mut hasher);
+    for input in inputs {
+        hash_mtimes(input, &mut hasher);
+    }
+
+    // The `Debug` output of `cmd` doesn't include its environment, so also
+    // hash what that's derived from: the configuration the `RUSTC_*` variables
+    // we set come from, and the flags inherited from our own environment.
+    if let Some(ref config) = build.flags.config {
+        hash_mtimes(config, &mut hasher);
+    }
+    build.flags.profile.hash(&mut hasher);
+    build.flags.incremental.hash(&mut hasher);
+    let mut vars = env::vars_os().filter(|&(ref k, _)| {
+        k.to_str().map_or(false, |k| {
+            k.starts_with("RUSTC") || k.starts_with("RUSTFLAGS") ||
+                k.starts_with("RUSTDOCFLAGS") || k.starts_with("RUST_TEST_")
+        })
+    }).collect::<Vec<_>>();
+    vars.sort();
+    vars.hash(&mut hasher);
+    let hash = hasher.finish().to_string();
+
+    if !build.flags.cmd.force_rerun() {
+        let mut contents = String::new();
+        if let Ok(mut f) = File::open(stamp) {
+            t!(f.read_to_string(&mut contents));
+        }
+        if contents == hash {
+            println!("Skipping, inputs unchanged since the last successful run");
+            build.cache_hits.set(build.cache_hits.get() + 1);
+            return None
+        }
+    }
+    build.cache_misses.set(build.cache_misses.get() + 1);
+
+    let _ = fs::remove_file(stamp);
+    Some(hash)
+}
+
+fn write_stamp(stamp: &Path, hash: &str) {
+    t!(fs::create_dir_all(stamp.parent().unwrap()));
+    t!(t!(File::create(stamp)).write_all(hash.as_bytes()));
+}
+
+/// The compiletest suites running in the background with
+/// `--parallel-suites`.
+#[derive(Default)]
+pub struct Suites {
+    running: usize,
+    finished: Option<(mpsc::Sender<()>, mpsc::Receiver<()>)>,
+    started: Vec<Suite>,
+}
+
+struct Suite {
+    name: String,
+    stamp: PathBuf,
+    hash: String,
+    // The number of failed attempts before the suite passed, or `None` if
+    // it failed every time.
+    thread: thread::JoinHandle<Option<u32>>,
+}
+
+/// Notifies `spawn_cached` that a suite finished, even if its thread panicked.
+struct Finished(mpsc::Sender<()>);
+
+impl Drop for Finished {
+    fn drop(&mut self) {
+        let _ = self.0.send(());
+    }
+}
+
+/// Like `try_run_cached`, but runs `cmd` in the background, with each line of
+/// its output prefixed by `prefix`. If `jobs` commands are running already
+/// this first waits for one of them to finish.
+///
+/// The results are collected by `finish_suites` once all steps were run.
+fn spawn_cached(build: &Build,
+                mut cmd: Command,
+                stamp: &Path,
+                inputs: &[PathBuf],
+                name: String,
+                prefix: String,
+                jobs: usize) {
+    let hash = match inputs_hash(build, &cmd, stamp, inputs) {
+        Some(hash) => hash,
+        None => return,
+    };
+    build.verbose(&format!("running: {:?}", cmd));
+
+    let mut suites = build.suites.borrow_mut();
+    if suites.finished.is_none() {
+        suites.finished = Some(mpsc::channel());
+    }
+    while suites.running >= jobs {
+        suites.finished.as_ref().unwrap().1.recv().unwrap();
+        suites.running -= 1;
+    }
+    let finished = Finished(suites.finished.as_ref().unwrap().0.clone());
+    let retries = build.flags.cmd.retry();
+    let thread = thread::spawn(move || {
+        let _finished = finished;
+        for attempt in 0..retries + 1 {
+            if run_prefixed(&mut cmd, &prefix) {
+                return Some(attempt)
+            }
+            if attempt < retries {
+                println!("{} command did not execute successfully, retrying ({}/{})",
+                         prefix, attempt + 1, retries);
+            }
+        }
+        println!("{} command did not execute successfully: {:?}", prefix, cmd);
+        None
+    });
+    suites.running += 1;
+    suites.started.push(Suite {
+        name: name,
+        stamp: stamp.to_path_buf(),
+        hash: hash,
+        thread: thread,
+    });
+}
+
+/// Runs `cmd`, printing each line of its output prefixed by `prefix`, and
+/// returns whether it succeeded.
+fn run_prefixed(cmd: &mut Command, prefix: &str) -> bool {
+    cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            println!("{} failed to execute {:?}: {}", prefix, cmd, e);
+            return false
+        }
+    };
+    let stderr = child.stderr.take().unwrap();
+    let stderr_prefix = prefix.to_string();
+    let stderr = thread::spawn(move || {
+        print_prefixed(stderr, &stderr_prefix, &mut io::stderr());
+    });
+    print_prefixed(child.stdout.take().unwrap(), prefix, &mut io::stdout());
+    let _ = stderr.join();
+    child.wait().map(|status| status.success()).unwrap_or(false)
+}
+
+fn print_prefixed<R: Read, W: Write>(output: R, prefix: &str, dst: &mut W) {
+    for line in BufReader::new(output).split(b'\n') {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => return,
+        };
+        // Each line is written at once, so lines of different suites don't
+        // get mixed up.
+        let _ = writeln!(dst, "{} {}", prefix, String::from_utf8_lossy(&line));
+    }
+}
+
+/// Waits for the suites started in the background by `spawn_cached`.
+///
+/// Their failures are postponed like with `--no-fail-fast`. Returns the names
+/// of the suites which failed, and of those which only passed when retried.
+pub fn finish_suites(build: &Build) -> (Vec<String>, Vec<String>) {
+    let started = mem::replace(&mut build.suites.borrow_mut().started, Vec::new());
+    let mut failed = Vec::new();
+    let mut flaky = Vec::new();
+    for suite in started {
+        match suite.thread.join().unwrap_or(None) {
+            Some(attempt) => {
+                write_stamp(&suite.stamp, &suite.hash);
+                if attempt > 0 {
+                    build.flaky_runs.set(build.flaky_runs.get() + 1);
+                    flaky.push(suite.name);
+                }
+            }
+            None => {
+                build.delayed_failures.set(build.delayed_failures.get() + 1);
+                failed.push(suite.name);
+            }
+        }
+    }
+    build.suites.borrow_mut().running = 0;
+    (failed, flaky)
+}
+
+/// Hashes the path and modification time of `path` and, if it's a directory,
+/// everything inside of it.
+fn hash_mtimes(path: &Path, hasher: &mut DefaultHasher) {
+    let meta = match fs::metadata(path) {
+        Ok(meta) => meta,
+        Err(_) => return,
+    };
+    path.hash(hasher);
+    if meta.is_dir() {
+        let mut entries = t!(fs::read_dir(path)).map(|e| t!(e).path())
+                                                  .collect::<Vec<_>>();
+        entries.sort();
+        for entry in entries {
+            hash_mtimes(&entry, hasher);
         }
     } else {
-        build.run_quiet(cmd);
+        let time = mtime(path);
+        time.seconds().hash(hasher);
+        time.nanoseconds().hash(hasher);
     }
 }
 
+fn try_run_quiet(build: &Build, cmd: &mut Command) {
+    retry(build, cmd, &|cmd| build.try_run_quiet(cmd), &|cmd| build.run_quiet(cmd));
+}
+
+/// Returns whether a shard other than the first is being run with
+/// `--test-shard`.
+///
+/// Only the compiletest suites are split into shards, all other tests are run
+/// by the first shard alone.
+fn later_shard(build: &Build) -> bool {
+    build.flags.cmd.test_shard().map_or(false, |(k, _)| k != 1)
+}
+
 /// Runs the `linkchecker` tool as compiled in `stage` by the `host` compiler.
 ///
 /// This tool in `src/tools` will verify the validity of all our links in the
 /// documentation to ensure we don't have a bunch of dead ones.
 pub fn linkcheck(build: &Build, host: &str) {
+    if later_shard(build) {
+        return
+    }
     println!("Linkcheck ({})", host);
     let compiler = Compiler::new(0, host);
 
@@ -98,6 +366,9 @@ pub fn linkcheck(build: &Build, host: &str) {
 /// This tool in `src/tools` will check out a few Rust projects and run `cargo
 /// test` to ensure that we don't regress the test suites there.
 pub fn cargotest(build: &Build, stage: u32, host: &str) {
+    if later_shard(build) {
+        return
+    }
     let compiler = Compiler::new(stage, host);
 
     // Note that this is a short, cryptic, and not scoped directory name. This
@@ -117,6 +388,9 @@ pub fn cargotest(build: &Build, stage: u32, host: &str) {
 
 /// Runs `cargo test` for `cargo` packaged with Rust.
 pub fn cargo(build: &Build, stage: u32, host: &str) {
+    if later_shard(build) {
+        return
+    }
     let ref compiler = Compiler::new(stage, host);
 
     // Configure PATH to find the right rustc. NB. we have to use PATH
@@ -148,6 +422,9 @@ pub fn cargo(build: &Build, stage: u32, host: &str) {
 /// otherwise just implements a few lint-like checks that are specific to the
 /// compiler itself.
 pub fn tidy(build: &Build, host: &str) {
+    if later_shard(build) {
+        return
+    }
     let _folder = build.fold_output(|| "tidy");
     println!("tidy check ({})", host);
     let compiler = Compiler::new(0, host);
@@ -249,6 +526,14 @@ pub fn compiletest(build: &Build,
 
     cmd.args(&build.flags.cmd.test_args());
 
+    if let Some((k, n)) = build.flags.cmd.test_shard() {
+        cmd.arg("--test-shard").arg(format!("{}/{}", k, n));
+    }
+
+    if let Some(code) = build.flags.cmd.error_code() {
+        cmd.arg("--error-code").arg(code);
+    }
+
     if build.is_verbose() {
         cmd.arg("--verbose");
     }
@@ -316,8 +601,30 @@ pub fn compiletest(build: &Build,
 
     build.ci_env.force_coloring_in_ci(&mut cmd);
 
+    let stamp = testdir(build, compiler.host).join(suite)
+        .join(format!("stage{}-{}-{}.stamp", compiler.stage, target, mode));
+    // Besides the tests and the compiler, the results depend on compiletest
+    // itself and on the scripts in `src/etc` some suites run, like
+    // htmldocck.py and the debugger scripts.
+    let inputs = [build.src.join("src/test").join(suite),
+                  build.rustc_libdir(compiler),
+                  build.sysroot_libdir(compiler, target),
+                  build.tool(&Compiler::new(0, compiler.host), "compiletest"),
+                  build.src.join("src/etc")];
+
+    if let Some(jobs) = build.flags.cmd.parallel_suites() {
+        let prefix = if target == compiler.host {
+            format!("[{}]", suite)
+        } else {
+            format!("[{} {}]", suite, target)
+        };
+        let name = format!("src/test/{} (stage{}, {} -> {})",
+                           suite, compiler.stage, compiler.host, target);
+        return spawn_cached(build, cmd, &stamp, &inputs, name, prefix, jobs);
+    }
+
     let _time = util::timeit();
-    try_run(build, &mut cmd);
+    try_run_cached(build, &mut cmd, &stamp, &inputs);
 }
 
 /// Run `rustdoc --test` for all documentation in `src/doc`.
@@ -326,6 +633,9 @@ pub fn compiletest(build: &Build,
 /// located in `src/doc`. The `rustdoc` that's run is the one that sits next to
 /// `compiler`.
 pub fn docs(build: &Build, compiler: &Compiler) {
+    if later_shard(build) {
+        return
+    }
     // Do a breadth-first traversal of the `src/doc` directory and just run
     // tests for all files that end in `*.md`
     let mut stack = vec![build.src.join("src/doc")];
@@ -359,6 +669,9 @@ pub fn docs(build: &Build, compiler: &Compiler) {
 /// generate a markdown file from the error indexes of the code base which is
 /// then passed to `rustdoc --test`.
 pub fn error_index(build: &Build, compiler: &Compiler) {
+    if later_shard(build) {
+        return
+    }
     let _folder = build.fold_output(|| "test_error_index");
     println!("Testing error-index stage{}", compiler.stage);
 
@@ -416,6 +729,9 @@ pub fn krate(build: &Build,
              mode: Mode,
              test_kind: TestKind,
              krate: Option<&str>) {
+    if later_shard(build) {
+        return
+    }
     let (name, path, features, root) = match mode {
         Mode::Libstd => {
             ("libstd", "src/libstd", build.std_features(), "std")
@@ -457,9 +773,15 @@ pub fn krate(build: &Build,
         cargo.arg("--no-fail-fast");
     }
 
+    // Source directories of all crates being tested, used to decide whether
+    // the test results are still up to date.
+    let mut inputs = vec![build.sysroot_libdir(&compiler, target)];
     match krate {
         Some(krate) => {
             cargo.arg("-p").arg(krate);
+            if let Some(krate) = build.crates.get(krate) {
+                inputs.push(krate.path.clone());
+            }
         }
         None => {
             let mut visited = HashSet::new();
@@ -476,6 +798,7 @@ pub fn krate(build: &Build,
                 // messing with various mtime calculations and such.
                 if !name.contains("jemalloc") && name != "build_helper" {
                     cargo.arg("-p").arg(&format!("{}:0.0.0", name));
+                    inputs.push(build.crates[name].path.clone());
                 }
                 for dep in build.crates[name].deps.iter() {
                     if visited.insert(dep) {
@@ -515,7 +838,17 @@ pub fn krate(build: &Build,
         krate_remote(build, &compiler, target, mode);
     } else {
         cargo.args(&build.flags.cmd.test_args());
-        try_run(build, &mut cargo);
+        match test_kind {
+            TestKind::Test => {
+                let stamp = testdir(build, compiler.host)
+                    .join(format!("{}-stage{}-{}-{}.stamp", name, compiler.stage,
+                                  target, krate.unwrap_or("all")));
+                try_run_cached(build, &mut cargo, &stamp, &inputs);
+            }
+            TestKind::Bench => {
+                try_run(build, &mut cargo);
+            }
+        }
     }
 }
 
@@ -616,6 +949,9 @@ pub fn remote_copy_libs(build: &Build, compiler: &Compiler, target: &str) {
 
 /// Run "distcheck", a 'make check' from a tarball
 pub fn distcheck(build: &Build) {
+    if later_shard(build) {
+        return
+    }
     if build.build != "x86_64-unknown-linux-gnu" {
         return
     }
@@ -668,6 +1004,9 @@ pub fn distcheck(build: &Build) {
 
 /// Test the build system itself
 pub fn bootstrap(build: &Build) {
+    if later_shard(build) {
+        return
+    }
     let mut cmd = Command::new(&build.initial_cargo);
     cmd.arg("test")
        .current_dir(build.src.join("src/bootstrap"))
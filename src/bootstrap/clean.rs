@@ -14,14 +14,31 @@
 //! artifacts to prepare for a fresh build. Currently doesn't remove the
 //! `build/cache` directory (download cache) or the `build/$target/llvm`
 //! directory as we want that cached between builds.
+//!
+//! Alternatively only the output of some stages and parts of the build can
+//! be removed, see `clean_selected`.
 
 use std::fs;
 use std::io::{self, ErrorKind};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::process;
 
 use Build;
 
-pub fn clean(build: &Build) {
+/// The parts of the build which can be cleaned individually, with the
+/// suffix of their per-stage output directories (see `Build::stage_out`).
+const PARTS: &'static [(&'static str, &'static str)] = &[
+    ("libstd", "-std"),
+    ("libtest", "-test"),
+    ("librustc", "-rustc"),
+    ("tools", "-tools"),
+];
+
+pub fn clean(build: &Build, paths: &[PathBuf]) {
+    if paths.len() > 0 || build.flags.stage.is_some() {
+        return clean_selected(build, paths);
+    }
+
     rm_rf("tmp".as_ref());
     rm_rf(&build.out.join("tmp"));
     rm_rf(&build.out.join("dist"));
@@ -43,6 +60,61 @@ pub fn clean(build: &Build) {
     }
 }
 
+/// Removes the output of the parts of the build given by `paths`, for the
+/// stage passed with `--stage` or for all stages.
+///
+/// Removing an output directory also removes the stamp files that record
+/// what was built in it, so the next build rebuilds that part and, as their
+/// stamps are then newer, everything depending on it. Without any paths all
+/// output of the stage is removed, including its sysroot. LLVM isn't built
+/// in stages, and is only removed if `src/llvm` is passed explicitly.
+fn clean_selected(build: &Build, paths: &[PathBuf]) {
+    let stages = match build.flags.stage {
+        Some(stage) => vec![stage],
+        None => vec![0, 1, 2],
+    };
+
+    let mut suffixes = Vec::new();
+    let mut llvm = false;
+    for path in paths {
+        if path.ends_with("llvm") {
+            llvm = true;
+            continue
+        }
+        match PARTS.iter().find(|&&(name, _)| path.ends_with(name)) {
+            Some(&(_, suffix)) => suffixes.push(suffix),
+            None => {
+                println!("don't know how to clean {}, the paths which can be cleaned \
+                          are src/libstd, src/libtest, src/librustc, src/tools and src/llvm",
+                         path.display());
+                process::exit(1);
+            }
+        }
+    }
+    if paths.is_empty() {
+        suffixes.extend(PARTS.iter().map(|&(_, suffix)| suffix));
+    }
+
+    for host in build.config.host.iter() {
+        let out = build.out.join(host);
+        if llvm {
+            println!("Removing LLVM for {}", host);
+            rm_rf(&out.join("llvm"));
+        }
+        for stage in stages.iter() {
+            for suffix in suffixes.iter() {
+                rm_rf(&out.join(format!("stage{}{}", stage, suffix)));
+            }
+            if paths.is_empty() {
+                match *stage {
+                    0 => rm_rf(&out.join("stage0-sysroot")),
+                    n => rm_rf(&out.join(format!("stage{}", n))),
+                }
+            }
+        }
+    }
+}
+
 fn rm_rf(path: &Path) {
     match path.symlink_metadata() {
         Err(e) => {
@@ -20,6 +20,7 @@ use std::env;
 use std::fs::{self, File};
 use std::io::BufReader;
 use std::io::prelude::*;
+use std::iter;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 use std::str;
@@ -29,6 +30,7 @@ use filetime::FileTime;
 use rustc_serialize::json;
 
 use channel::GitInfo;
+use native;
 use util::{exe, libdir, is_dylib, copy};
 use {Build, Compiler, Mode};
 
@@ -38,6 +40,13 @@ use {Build, Compiler, Mode};
 /// using the `compiler` targeting the `target` architecture. The artifacts
 /// created will also be linked into the sysroot directory.
 pub fn std(build: &Build, target: &str, compiler: &Compiler) {
+    // A kept standard library is outdated if its compiler was rebuilt since.
+    if keep_stage(build, compiler, target, Mode::Libstd,
+                  &libstd_stamp(build, compiler, target),
+                  &build.compiler_path(compiler)) {
+        return
+    }
+
     let libdir = build.sysroot_libdir(compiler, target);
     t!(fs::create_dir_all(&libdir));
 
@@ -230,6 +239,14 @@ pub fn test_link(build: &Build,
 /// the `compiler` targeting the `target` architecture. The artifacts
 /// created will also be linked into the sysroot directory.
 pub fn rustc(build: &Build, target: &str, compiler: &Compiler) {
+    // A kept compiler is outdated if the libraries it links to were rebuilt
+    // since.
+    if keep_stage(build, compiler, target, Mode::Librustc,
+                  &librustc_stamp(build, compiler, target),
+                  &libtest_stamp(build, compiler, target)) {
+        return
+    }
+
     let _folder = build.fold_output(|| format!("stage{}-rustc", compiler.stage));
     println!("Building stage{} compiler artifacts ({} -> {})",
              compiler.stage, compiler.host, target);
@@ -272,6 +289,27 @@ pub fn rustc(build: &Build, target: &str, compiler: &Compiler) {
     if !build.unstable_features() {
         cargo.env("CFG_DISABLE_UNSTABLE_FEATURES", "1");
     }
+    // The C++ parts of librustc_llvm are compiled by the `gcc` crate, which
+    // understands these launchers in front of the compiler. It only accepts
+    // their bare names though, so a launcher given as a path is found by
+    // putting its directory first in PATH.
+    if let Some(ref launcher) = build.config.ccache {
+        let name = native::launcher_name(launcher);
+        let launcher = Path::new(launcher);
+        if !target.contains("msvc") && ["ccache", "sccache", "distcc"].contains(&name) {
+            cargo.env(format!("CXX_{}", target),
+                      format!("{} {}", name, build.cxx(target).unwrap().display()));
+            match launcher.parent() {
+                Some(dir) if dir != Path::new("") => {
+                    let path = env::var_os("PATH").unwrap_or_default();
+                    let path = env::join_paths(iter::once(dir.to_path_buf())
+                                                   .chain(env::split_paths(&path)));
+                    cargo.env("PATH", t!(path));
+                }
+                _ => {}
+            }
+        }
+    }
     // Flag that rust llvm is in use
     if build.is_rust_llvm(target) {
         cargo.env("LLVM_RUSTLLVM", "1");
@@ -324,6 +362,46 @@ fn libstd_stamp(build: &Build, compiler: &Compiler, target: &str) -> PathBuf {
     build.cargo_out(compiler, Mode::Libstd, target).join(".libstd.stamp")
 }
 
+/// Returns whether the `mode` artifacts compiled by `compiler` for `target`
+/// are kept from a previous build, as requested with `--keep-stage-std` or
+/// `--keep-stage-rustc`.
+///
+/// Artifacts which were never built can't be kept and are built anyway. If
+/// `input`, which the artifacts were built from, changed since `stamp` was
+/// written, they are kept but likely incompatible, which is warned about.
+fn keep_stage(build: &Build,
+              compiler: &Compiler,
+              target: &str,
+              mode: Mode,
+              stamp: &Path,
+              input: &Path) -> bool {
+    let (keep, what, flag) = match mode {
+        Mode::Libstd => (build.flags.keep_stage_std, "std", "--keep-stage-std"),
+        Mode::Librustc => (build.flags.keep_stage_rustc, "compiler", "--keep-stage-rustc"),
+        _ => panic!("only std and the compiler can be kept"),
+    };
+    if !keep.map_or(false, |stage| compiler.stage <= stage) {
+        return false
+    }
+    if !stamp.exists() {
+        println!("warning: stage{} {} artifacts ({} -> {}) have not been built yet, \
+                  building them despite {}",
+                 compiler.stage, what, compiler.host, target, flag);
+        return false
+    }
+    println!("Keeping stage{} {} artifacts ({} -> {})",
+             compiler.stage, what, compiler.host, target);
+    if mtime(input) > mtime(stamp) {
+        println!("\n\
+                  WARNING: the stage{} {} artifacts ({} -> {}) kept due to {} are\n\
+                  WARNING: older than {}, which they were built from.\n\
+                  WARNING: They are likely incompatible with it, and the build or\n\
+                  WARNING: its results may fail in surprising ways.\n",
+                 compiler.stage, what, compiler.host, target, flag, input.display());
+    }
+    true
+}
+
 /// Cargo's output path for libtest in a given stage, compiled by a particular
 /// compiler for the specified target.
 fn libtest_stamp(build: &Build, compiler: &Compiler, target: &str) -> PathBuf {
@@ -332,7 +410,7 @@ fn libtest_stamp(build: &Build, compiler: &Compiler, target: &str) -> PathBuf {
 
 /// Cargo's output path for librustc in a given stage, compiled by a particular
 /// compiler for the specified target.
-fn librustc_stamp(build: &Build, compiler: &Compiler, target: &str) -> PathBuf {
+pub fn librustc_stamp(build: &Build, compiler: &Compiler, target: &str) -> PathBuf {
     build.cargo_out(compiler, Mode::Librustc, target).join(".librustc.stamp")
 }
 
@@ -405,17 +483,21 @@ pub fn assemble_rustc(build: &Build, stage: u32, host: &str) {
 /// `sysroot_dst` provided.
 fn add_to_sysroot(sysroot_dst: &Path, stamp: &Path) {
     t!(fs::create_dir_all(&sysroot_dst));
+    for path in read_stamp_file(stamp) {
+        copy(&path, &sysroot_dst.join(path.file_name().unwrap()));
+    }
+}
+
+/// Returns the paths of the artifacts listed in `stamp`.
+pub fn read_stamp_file(stamp: &Path) -> Vec<PathBuf> {
     let mut contents = Vec::new();
     t!(t!(File::open(stamp)).read_to_end(&mut contents));
     // This is the method we use for extracting paths from the stamp file passed to us. See
     // run_cargo for more information (in this file).
-    for part in contents.split(|b| *b == 0) {
-        if part.is_empty() {
-            continue
-        }
-        let path = Path::new(t!(str::from_utf8(part)));
-        copy(&path, &sysroot_dst.join(path.file_name().unwrap()));
-    }
+    contents.split(|b| *b == 0)
+        .filter(|part| !part.is_empty())
+        .map(|part| PathBuf::from(t!(str::from_utf8(part))))
+        .collect()
 }
 
 /// Build a tool in `src/tools`
@@ -479,8 +561,7 @@ pub fn tool(build: &Build, stage: u32, target: &str, tool: &str) {
 // Avoiding a dependency on winapi to keep compile times down
 #[cfg(unix)]
 fn stderr_isatty() -> bool {
-    use libc;
-    unsafe { libc::isatty(libc::STDERR_FILENO) != 0 }
+    unsafe { ::libc::isatty(::libc::STDERR_FILENO) != 0 }
 }
 #[cfg(windows)]
 fn stderr_isatty() -> bool {
@@ -51,6 +51,10 @@ pub struct Config {
     pub extended: bool,
     pub sanitizers: bool,
     pub profiler: bool,
+    pub stats: bool,
+    pub hermetic: bool,
+    pub hermetic_lock: Option<PathBuf>,
+    pub test_workers: Vec<String>,
 
     // llvm codegen options
     pub llvm_assertions: bool,
@@ -63,11 +67,18 @@ pub struct Config {
     pub llvm_experimental_targets: Option<String>,
     pub llvm_link_jobs: Option<u32>,
     pub llvm_clean_rebuild: bool,
+    pub llvm_cache: bool,
+    pub llvm_cache_dir: Option<PathBuf>,
 
     // rust codegen options
     pub rust_optimize: bool,
     pub rust_codegen_units: u32,
+    pub rust_codegen_units_std: Option<u32>,
+    pub rust_codegen_units_rustc: Option<u32>,
     pub rust_debug_assertions: bool,
+    pub rust_debug_assertions_std: Option<bool>,
+    pub rust_debug_assertions_rustc: Option<bool>,
+    pub rust_debug_assertions_stage: HashMap<u32, bool>,
     pub rust_debuginfo: bool,
     pub rust_debuginfo_lines: bool,
     pub rust_debuginfo_only_std: bool,
@@ -77,6 +88,7 @@ pub struct Config {
     pub rust_optimize_tests: bool,
     pub rust_debuginfo_tests: bool,
     pub rust_dist_src: bool,
+    pub dist_rustc_dev: bool,
 
     pub build: String,
     pub host: Vec<String>,
@@ -140,6 +152,7 @@ pub struct Target {
 /// `Config` structure.
 #[derive(RustcDecodable, Default)]
 struct TomlConfig {
+    profile: Option<String>,
     build: Option<Build>,
     install: Option<Install>,
     llvm: Option<Llvm>,
@@ -159,7 +172,7 @@ struct Build {
     low_priority: Option<bool>,
     compiler_docs: Option<bool>,
     docs: Option<bool>,
-    submodules: Option<bool>,
+    submodules: Option<StringOrBool>,
     gdb: Option<String>,
     locked_deps: Option<bool>,
     vendor: Option<bool>,
@@ -171,6 +184,10 @@ struct Build {
     sanitizers: Option<bool>,
     profiler: Option<bool>,
     openssl_static: Option<bool>,
+    stats: Option<bool>,
+    hermetic: Option<bool>,
+    hermetic_lock: Option<String>,
+    test_workers: Vec<String>,
 }
 
 /// TOML representation of various global install decisions.
@@ -188,6 +205,7 @@ struct Install {
 #[derive(RustcDecodable, Default)]
 struct Llvm {
     ccache: Option<StringOrBool>,
+    compiler_launcher: Option<String>,
     ninja: Option<bool>,
     assertions: Option<bool>,
     optimize: Option<bool>,
@@ -198,6 +216,8 @@ struct Llvm {
     experimental_targets: Option<String>,
     link_jobs: Option<u32>,
     clean_rebuild: Option<bool>,
+    cache: Option<bool>,
+    cache_dir: Option<String>,
 }
 
 #[derive(RustcDecodable, Default, Clone)]
@@ -206,9 +226,10 @@ struct Dist {
     gpg_password_file: Option<String>,
     upload_addr: Option<String>,
     src_tarball: Option<bool>,
+    rustc_dev: Option<bool>,
 }
 
-#[derive(RustcDecodable)]
+#[derive(RustcDecodable, Clone)]
 enum StringOrBool {
     String(String),
     Bool(bool),
@@ -225,7 +246,14 @@ impl Default for StringOrBool {
 struct Rust {
     optimize: Option<bool>,
     codegen_units: Option<u32>,
+    codegen_units_std: Option<u32>,
+    codegen_units_rustc: Option<u32>,
     debug_assertions: Option<bool>,
+    debug_assertions_std: Option<bool>,
+    debug_assertions_rustc: Option<bool>,
+    debug_assertions_stage0: Option<bool>,
+    debug_assertions_stage1: Option<bool>,
+    debug_assertions_stage2: Option<bool>,
     debuginfo: Option<bool>,
     debuginfo_lines: Option<bool>,
     debuginfo_only_std: Option<bool>,
@@ -255,7 +283,7 @@ struct TomlTarget {
 }
 
 impl Config {
-    pub fn parse(build: &str, file: Option<PathBuf>) -> Config {
+    pub fn parse(build: &str, file: Option<PathBuf>, profile: Option<String>) -> Config {
         let mut config = Config::default();
         config.llvm_optimize = true;
         config.use_jemalloc = true;
@@ -299,6 +327,12 @@ impl Config {
             }
         }).unwrap_or_else(|| TomlConfig::default());
 
+        // Profiles only change the defaults, so they're applied before
+        // anything explicitly configured in `config.toml`.
+        if let Some(ref profile) = profile.or(toml.profile.clone()) {
+            apply_profile(&mut config, profile);
+        }
+
         let build = toml.build.clone().unwrap_or(Build::default());
         set(&mut config.build, build.build.clone());
         config.host.push(config.build.clone());
@@ -318,7 +352,18 @@ impl Config {
         set(&mut config.low_priority, build.low_priority);
         set(&mut config.compiler_docs, build.compiler_docs);
         set(&mut config.docs, build.docs);
-        set(&mut config.submodules, build.submodules);
+        // Submodules are checked out by bootstrap.py, which also implements
+        // the shallow and mirror modes.
+        match build.submodules {
+            Some(StringOrBool::String(ref s)) => {
+                if s != "shallow" && !s.starts_with("mirror:") {
+                    panic!("invalid value for `build.submodules`: {}", s);
+                }
+                config.submodules = true;
+            }
+            Some(StringOrBool::Bool(b)) => config.submodules = b,
+            None => {}
+        }
         set(&mut config.locked_deps, build.locked_deps);
         set(&mut config.vendor, build.vendor);
         set(&mut config.full_bootstrap, build.full_bootstrap);
@@ -326,6 +371,10 @@ impl Config {
         set(&mut config.verbose, build.verbose);
         set(&mut config.sanitizers, build.sanitizers);
         set(&mut config.profiler, build.profiler);
+        set(&mut config.stats, build.stats);
+        set(&mut config.hermetic, build.hermetic);
+        config.hermetic_lock = build.hermetic_lock.clone().map(PathBuf::from);
+        config.test_workers = build.test_workers.clone();
         set(&mut config.openssl_static, build.openssl_static);
 
         if let Some(ref install) = toml.install {
@@ -347,6 +396,9 @@ impl Config {
                 }
                 Some(StringOrBool::Bool(false)) | None => {}
             }
+            if let Some(ref launcher) = llvm.compiler_launcher {
+                config.ccache = Some(launcher.clone());
+            }
             set(&mut config.ninja, llvm.ninja);
             set(&mut config.llvm_assertions, llvm.assertions);
             set(&mut config.llvm_optimize, llvm.optimize);
@@ -357,6 +409,11 @@ impl Config {
             config.llvm_targets = llvm.targets.clone();
             config.llvm_experimental_targets = llvm.experimental_targets.clone();
             config.llvm_link_jobs = llvm.link_jobs;
+            set(&mut config.llvm_cache, llvm.cache);
+            config.llvm_cache_dir = llvm.cache_dir.clone().map(PathBuf::from);
+            if config.llvm_cache_dir.is_some() {
+                config.llvm_cache = true;
+            }
         }
 
         if let Some(ref rust) = toml.rust {
@@ -377,10 +434,21 @@ impl Config {
             config.rustc_default_ar = rust.default_ar.clone();
             config.musl_root = rust.musl_root.clone().map(PathBuf::from);
 
-            match rust.codegen_units {
-                Some(0) => config.rust_codegen_units = num_cpus::get() as u32,
-                Some(n) => config.rust_codegen_units = n,
-                None => {}
+            if let Some(n) = rust.codegen_units {
+                config.rust_codegen_units = parse_codegen_units(n);
+            }
+            config.rust_codegen_units_std = rust.codegen_units_std.map(parse_codegen_units);
+            config.rust_codegen_units_rustc = rust.codegen_units_rustc.map(parse_codegen_units);
+
+            config.rust_debug_assertions_std = rust.debug_assertions_std;
+            config.rust_debug_assertions_rustc = rust.debug_assertions_rustc;
+            let stages = [rust.debug_assertions_stage0,
+                          rust.debug_assertions_stage1,
+                          rust.debug_assertions_stage2];
+            for (stage, debug_assertions) in stages.iter().enumerate() {
+                if let Some(debug_assertions) = *debug_assertions {
+                    config.rust_debug_assertions_stage.insert(stage as u32, debug_assertions);
+                }
             }
         }
 
@@ -411,6 +479,7 @@ impl Config {
             config.dist_gpg_password_file = t.gpg_password_file.clone().map(PathBuf::from);
             config.dist_upload_addr = t.upload_addr.clone();
             set(&mut config.rust_dist_src, t.src_tarball);
+            set(&mut config.dist_rustc_dev, t.rustc_dev);
         }
 
         let cwd = t!(env::current_dir());
@@ -500,6 +569,7 @@ impl Config {
                 ("SANITIZERS", self.sanitizers),
                 ("PROFILER", self.profiler),
                 ("DIST_SRC", self.rust_dist_src),
+                ("DIST_RUSTC_DEV", self.dist_rustc_dev),
                 ("CARGO_OPENSSL_STATIC", self.openssl_static),
             }
 
@@ -686,6 +756,60 @@ fn parse_configure_path(path: &str) -> PathBuf {
     win_path.into()
 }
 
+/// Names of the profiles accepted by `--profile` and the `profile` key of
+/// `config.toml`.
+const PROFILES: &[&str] = &["compiler", "library", "dist", "ci"];
+
+/// Layers the defaults of the named configuration profile on top of `config`.
+///
+/// * `compiler` - for working on the compiler: debug assertions in both rustc
+///   and LLVM, and no documentation.
+/// * `library` - for working on the standard library: debug assertions, but
+///   an optimized release LLVM and no documentation.
+/// * `dist` - for producing release artifacts: the extended set of tools and
+///   all documentation.
+/// * `ci` - what our continuous integration uses: assertions everywhere and
+///   a locked `Cargo.lock`.
+fn apply_profile(config: &mut Config, profile: &str) {
+    match profile {
+        "compiler" => {
+            config.rust_debug_assertions = true;
+            config.llvm_assertions = true;
+            config.docs = false;
+            config.compiler_docs = false;
+        }
+        "library" => {
+            config.rust_debug_assertions = true;
+            config.docs = false;
+            config.compiler_docs = false;
+        }
+        "dist" => {
+            config.extended = true;
+            config.docs = true;
+            config.rust_dist_src = true;
+        }
+        "ci" => {
+            config.rust_debug_assertions = true;
+            config.llvm_assertions = true;
+            config.locked_deps = true;
+        }
+        _ => {
+            println!("unknown profile `{}`, expected one of: {}",
+                     profile, PROFILES.join(", "));
+            process::exit(2);
+        }
+    }
+}
+
+/// A configured number of codegen units of 0 means "the number of cores on
+/// this machine".
+fn parse_codegen_units(n: u32) -> u32 {
+    match n {
+        0 => num_cpus::get() as u32,
+        n => n,
+    }
+}
+
 fn set<T>(field: &mut T, val: Option<T>) {
     if let Some(v) = val {
         *field = v;
@@ -28,6 +28,7 @@ use build_helper::output;
 
 use {Build, Compiler, Mode};
 use channel;
+use compile;
 use util::{cp_r, libdir, is_dylib, cp_filtered, copy, exe};
 
 pub fn pkgname(build: &Build, component: &str) -> String {
@@ -409,6 +410,14 @@ pub fn std(build: &Build, compiler: &Compiler, target: &str) {
     src.pop(); // Remove the trailing /lib folder from the sysroot_libdir
     cp_r(&src, &dst);
 
+    // The compiler crates are shipped in the `rustc-dev` component instead if
+    // that's built, the two components mustn't contain the same files.
+    if build.config.dist_rustc_dev && build.config.host.iter().any(|t| t == target) {
+        for path in rustc_dev_files(build, compiler, target) {
+            t!(fs::remove_file(dst.join("lib").join(path.file_name().unwrap())));
+        }
+    }
+
     let mut cmd = rust_installer(build);
     cmd.arg("generate")
        .arg("--product-name=Rust")
@@ -480,6 +489,75 @@ pub fn analysis(build: &Build, compiler: &Compiler, target: &str) {
     t!(fs::remove_dir_all(&image));
 }
 
+/// Returns the compiler crates which were linked into the sysroot of
+/// `compiler` for `target`.
+fn rustc_dev_files(build: &Build, compiler: &Compiler, target: &str) -> Vec<PathBuf> {
+    // If not doing a full bootstrap the stage2 crates are simply copied from
+    // stage1, so that's the build which lists them.
+    let compiler = if build.force_use_stage1(compiler, target) {
+        Compiler::new(1, compiler.host)
+    } else {
+        compiler.clone()
+    };
+    compile::read_stamp_file(&compile::librustc_stamp(build, &compiler, target))
+}
+
+/// Creates the `rustc-dev` installer component.
+///
+/// This contains the compiler crates, librustc, libsyntax and all of their
+/// dependencies, as built for `target`, so that out-of-tree tools can link
+/// against them. The sources of the crates which are part of this repository
+/// are included in `lib/rustlib/rustc-src/rust`, laid out like in the source
+/// tree, so that their debuginfo can be mapped back to the sources.
+pub fn rustc_dev(build: &Build, compiler: &Compiler, target: &str) {
+    println!("Dist rustc-dev stage{} ({} -> {})", compiler.stage, compiler.host, target);
+
+    if compiler.host != build.build {
+        println!("\tskipping, not a build host");
+        return
+    }
+
+    let name = pkgname(build, "rustc-dev");
+    let image = tmpdir(build).join(format!("{}-{}-image", name, target));
+    let _ = fs::remove_dir_all(&image);
+
+    let files = rustc_dev_files(build, compiler, target);
+    let dst = image.join("lib/rustlib").join(target).join("lib");
+    t!(fs::create_dir_all(&dst));
+    for path in files.iter() {
+        copy(path, &dst.join(path.file_name().unwrap()));
+    }
+
+    // The crate `foo` built from this repository lives in `src/libfoo`, its
+    // artifacts are named `libfoo-<hash>.rlib`, or `foo-<hash>.dll` and the
+    // like on Windows.
+    let mut src_dirs = files.iter()
+        .filter_map(|path| path.file_name().and_then(|name| name.to_str()))
+        .filter_map(|name| name.rfind('-').map(|i| &name[..i]))
+        .map(|name| if name.starts_with("lib") { &name[3..] } else { name })
+        .map(|krate| format!("src/lib{}", krate))
+        .filter(|dir| build.src.join(dir).is_dir())
+        .collect::<Vec<_>>();
+    src_dirs.sort();
+    src_dirs.dedup();
+    let src_dirs = src_dirs.iter().map(|dir| &dir[..]).collect::<Vec<_>>();
+    copy_src_dirs(build, &src_dirs, &[], &image.join("lib/rustlib/rustc-src/rust"));
+
+    let mut cmd = rust_installer(build);
+    cmd.arg("generate")
+       .arg("--product-name=Rust")
+       .arg("--rel-manifest-dir=rustlib")
+       .arg("--success-message=rustc-dev-installed.")
+       .arg("--image-dir").arg(&image)
+       .arg("--work-dir").arg(&tmpdir(build))
+       .arg("--output-dir").arg(&distdir(build))
+       .arg(format!("--package-name={}-{}", name, target))
+       .arg(format!("--component-name=rustc-dev-{}", target))
+       .arg("--legacy-manifest-dirs=rustlib,cargo");
+    build.run(&mut cmd);
+    t!(fs::remove_dir_all(&image));
+}
+
 fn copy_src_dirs(build: &Build, src_dirs: &[&str], exclude_dirs: &[&str], dst_dir: &Path) {
     fn filter_fn(exclude_dirs: &[&str], dir: &str, path: &Path) -> bool {
         let spath = match path.to_str() {
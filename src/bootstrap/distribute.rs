@@ -0,0 +1,254 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Distribution of `./x.py test` across the workers in `build.test-workers`.
+//!
+//! Each of the N workers runs the same `./x.py test` command line in its own
+//! checkout with `--test-shard K/N` appended, so the compiletest suites are
+//! split between them and the other tests are run by the first worker only.
+//! A worker is reached through an `Executor`:
+//!
+//! * `ssh://[user@]host[:port]/path/to/checkout` runs `x.py` over ssh.
+//! * `http://host[:port]` posts `{"commit": ..., "args": [...]}` as JSON to
+//!   `/test`, and expects the output of `x.py` in response, followed by a
+//!   last line `exit status: <code>`. `GET /artifacts` returns the test
+//!   output directory of the worker's last run as a tar archive.
+//!
+//! The checkouts of the workers must be at the same commit as the local one.
+//! The output of each shard is printed as it finishes, and the test output
+//! directories of the shards which failed are collected into
+//! `build/workers/shard-K` for inspection.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Output};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Instant;
+
+use rustc_serialize::json;
+
+use Build;
+
+/// A way of running `x.py` on a worker.
+pub trait Executor: Send {
+    /// Describes the worker in progress messages.
+    fn describe(&self) -> String;
+
+    /// Returns the command which runs `x.py` with `args` on the worker, after
+    /// making sure that its checkout is at `commit` if that's known.
+    fn command(&self, commit: Option<&str>, args: &[String]) -> Command;
+
+    /// Returns whether the command returned by `command` succeeded, along
+    /// with the output of `x.py`.
+    fn finish(&self, output: &Output) -> (bool, String) {
+        let mut out = String::from_utf8_lossy(&output.stdout).into_owned();
+        out.push_str(&String::from_utf8_lossy(&output.stderr));
+        (output.status.success(), out)
+    }
+
+    /// Copies the test output directory of the worker's build of `triple`,
+    /// `build/<triple>/test`, to `dst`.
+    fn collect(&self, build: &Build, triple: &str, dst: &Path) -> bool;
+}
+
+struct Ssh {
+    host: String,
+    port: Option<String>,
+    dir: String,
+}
+
+impl Executor for Ssh {
+    fn describe(&self) -> String {
+        format!("{}:{}", self.host, self.dir)
+    }
+
+    fn command(&self, commit: Option<&str>, args: &[String]) -> Command {
+        let mut script = format!("cd {} || exit 1; ", quote(&self.dir));
+        if let Some(commit) = commit {
+            script.push_str(&format!("if [ \"$(git rev-parse HEAD)\" != {0} ]; then \
+                                      echo 'the checkout is not at commit {0}' >&2; \
+                                      exit 1; fi; ",
+                                     quote(commit)));
+        }
+        script.push_str("exec python x.py");
+        for arg in args {
+            script.push(' ');
+            script.push_str(&quote(arg));
+        }
+
+        let mut cmd = Command::new("ssh");
+        if let Some(ref port) = self.port {
+            cmd.arg("-p").arg(port);
+        }
+        cmd.arg(&self.host).arg(script);
+        cmd
+    }
+
+    fn collect(&self, build: &Build, triple: &str, dst: &Path) -> bool {
+        let mut cmd = Command::new("scp");
+        if let Some(ref port) = self.port {
+            cmd.arg("-P").arg(port);
+        }
+        cmd.arg("-rq")
+           .arg(format!("{}:{}/build/{}/test", self.host, self.dir, triple))
+           .arg(dst);
+        build.try_run(&mut cmd)
+    }
+}
+
+struct Http {
+    url: String,
+}
+
+#[derive(RustcEncodable)]
+struct Job<'a> {
+    commit: Option<&'a str>,
+    args: &'a [String],
+}
+
+impl Executor for Http {
+    fn describe(&self) -> String {
+        self.url.clone()
+    }
+
+    fn command(&self, commit: Option<&str>, args: &[String]) -> Command {
+        let job = t!(json::encode(&Job { commit: commit, args: args }));
+        let mut cmd = Command::new("curl");
+        cmd.arg("-sS")
+           .arg("-H").arg("Content-Type: application/json")
+           .arg("--data-binary").arg(job)
+           .arg(format!("{}/test", self.url));
+        cmd
+    }
+
+    fn finish(&self, output: &Output) -> (bool, String) {
+        let mut out = String::from_utf8_lossy(&output.stdout).into_owned();
+        out.push_str(&String::from_utf8_lossy(&output.stderr));
+        if !output.status.success() {
+            return (false, out)
+        }
+        // The exit status of `x.py` is the last line of the response.
+        let end = out.trim_right().len();
+        let start = out[..end].rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let success = &out[start..end] == "exit status: 0";
+        out.truncate(start);
+        (success, out)
+    }
+
+    fn collect(&self, build: &Build, _triple: &str, dst: &Path) -> bool {
+        let tarball = dst.with_extension("tar");
+        let ok = build.try_run(Command::new("curl")
+                                   .arg("-sSf")
+                                   .arg("-o").arg(&tarball)
+                                   .arg(format!("{}/artifacts", self.url)));
+        if !ok {
+            return false
+        }
+        t!(fs::create_dir_all(dst));
+        let ok = build.try_run(Command::new("tar")
+                                   .arg("-xf").arg(&tarball)
+                                   .arg("-C").arg(dst));
+        let _ = fs::remove_file(&tarball);
+        ok
+    }
+}
+
+/// Parses a worker from `build.test-workers`.
+fn executor(worker: &str) -> Box<Executor> {
+    if worker.starts_with("ssh://") {
+        let rest = &worker["ssh://".len()..];
+        let (host, dir) = match rest.find('/') {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => panic!("ssh worker `{}` doesn't specify the path of the checkout", worker),
+        };
+        let (host, port) = match host.rfind(':') {
+            Some(i) => (&host[..i], Some(host[i + 1..].to_string())),
+            None => (host, None),
+        };
+        Box::new(Ssh { host: host.to_string(), port: port, dir: dir.to_string() })
+    } else if worker.starts_with("http://") || worker.starts_with("https://") {
+        Box::new(Http { url: worker.trim_right_matches('/').to_string() })
+    } else {
+        panic!("unsupported test worker `{}`, expected an ssh:// or http:// URL", worker)
+    }
+}
+
+/// Quotes `s` for a POSIX shell.
+fn quote(s: &str) -> String {
+    format!("'{}'", s.replace("'", "'\\''"))
+}
+
+/// Implementation of `./x.py test` when `build.test-workers` is set, running
+/// one shard of the tests on each worker.
+pub fn test(build: &Build) {
+    let workers = build.config.test_workers.iter()
+        .map(|w| executor(w))
+        .collect::<Vec<_>>();
+    let shards = workers.len();
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let commit = build.rust_info.sha().map(|s| s.to_string());
+
+    println!("Distributing the tests across {} workers", shards);
+    let (tx, rx) = mpsc::channel();
+    for (i, worker) in workers.into_iter().enumerate() {
+        let shard = format!("{}/{}", i + 1, shards);
+        println!("    shard {}: {}", shard, worker.describe());
+        let mut args = args.clone();
+        args.push("--test-shard".to_string());
+        args.push(shard.clone());
+        let commit = commit.clone();
+        let tx = tx.clone();
+        thread::spawn(move || {
+            let start = Instant::now();
+            let mut cmd = worker.command(commit.as_ref().map(|s| &s[..]), &args);
+            let result = match cmd.output() {
+                Ok(output) => worker.finish(&output),
+                Err(e) => (false, format!("failed to execute {:?}: {}\n", cmd, e)),
+            };
+            tx.send((i, shard, worker, result, start.elapsed().as_secs())).unwrap();
+        });
+    }
+    drop(tx);
+
+    let mut failed = Vec::new();
+    for (i, shard, worker, (success, out), secs) in rx {
+        println!("\n---- shard {} on {}: {} in {}s ----\n",
+                 shard, worker.describe(), if success { "ok" } else { "failed" }, secs);
+        print!("{}", out);
+        if !success {
+            failed.push((i, shard, worker));
+        }
+    }
+
+    if failed.is_empty() {
+        println!("\nall {} shards passed", shards);
+        return
+    }
+
+    println!("\nThe following shards failed:");
+    for &(i, ref shard, ref worker) in failed.iter() {
+        let dst = artifacts_dir(build, i);
+        let _ = fs::remove_dir_all(&dst);
+        t!(fs::create_dir_all(dst.parent().unwrap()));
+        if worker.collect(build, &build.build, &dst) {
+            println!("    {} on {}, test output in {}", shard, worker.describe(), dst.display());
+        } else {
+            println!("    {} on {}, test output couldn't be collected",
+                     shard, worker.describe());
+        }
+    }
+    process::exit(1);
+}
+
+fn artifacts_dir(build: &Build, shard: usize) -> PathBuf {
+    build.out.join("workers").join(format!("shard-{}", shard + 1))
+}
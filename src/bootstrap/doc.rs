@@ -20,7 +20,7 @@
 use std::fs::{self, File};
 use std::io::prelude::*;
 use std::io;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 use {Build, Compiler, Mode};
@@ -345,6 +345,104 @@ pub fn rustc(build: &Build, stage: u32, target: &str) {
     cp_r(&my_out, &out);
 }
 
+/// Generate documentation for a single crate of the standard library or of the
+/// compiler.
+///
+/// Unlike `std` and `rustc` above this only runs rustdoc on `krate` itself.
+/// Documentation of its dependencies is not regenerated; whatever a previous
+/// run left in the output directory is linked to instead.
+pub fn krate(build: &Build, stage: u32, target: &str, mode: Mode, name: &str) {
+    let krate = &build.crates[name];
+    println!("Documenting stage{} {} ({})", stage, krate.name, target);
+    let out = build.doc_out(target);
+    t!(fs::create_dir_all(&out));
+    let compiler = Compiler::new(stage, &build.build);
+    let compiler = if build.force_use_stage1(&compiler, target) {
+        Compiler::new(1, compiler.host)
+    } else {
+        compiler
+    };
+    let out_dir = build.stage_out(&compiler, mode)
+                       .join(target).join("doc");
+    let rustdoc = build.rustdoc(&compiler);
+
+    // See docs in std above for why we symlink
+    let my_out = build.crate_doc_out(target);
+    build.clear_if_dirty(&my_out, &rustdoc);
+    t!(symlink_dir_force(&my_out, &out_dir));
+
+    let mut cargo = build.cargo(&compiler, mode, target, "doc");
+    match mode {
+        Mode::Libstd => {
+            cargo.arg("--manifest-path")
+                 .arg(build.src.join("src/libstd/Cargo.toml"))
+                 .arg("--features").arg(build.std_features());
+        }
+        Mode::Librustc => {
+            cargo.arg("--manifest-path")
+                 .arg(build.src.join("src/rustc/Cargo.toml"))
+                 .arg("--features").arg(build.rustc_features());
+        }
+        _ => panic!("can only document single crates of std or rustc"),
+    }
+    cargo.arg("--no-deps").arg("-p").arg(&krate.name);
+
+    // Like in std above, make sure rustdoc uses relative links to the
+    // documentation of the dependencies.
+    for dep in krate.deps.iter() {
+        t!(fs::create_dir_all(out_dir.join(dep.replace("-", "_"))));
+    }
+
+    build.run(&mut cargo);
+    cp_r(&my_out, &out);
+}
+
+/// Opens the documentation generated for `paths` in a web browser.
+///
+/// Documentation of a crate opens at the crate's root page, and that of the
+/// books at their first page. Anything else, including a plain `./x.py doc`,
+/// opens the index of all documentation.
+pub fn open(build: &Build, paths: &[PathBuf]) {
+    let out = build.doc_out(&build.build);
+    let mut pages = Vec::new();
+    for path in paths {
+        let krate = build.crates.values().find(|krate| krate.path.ends_with(path));
+        let page = match krate {
+            Some(krate) => out.join(krate.name.replace("-", "_")).join("index.html"),
+            None => {
+                match path.file_name() {
+                    Some(name) => out.join(name).join("index.html"),
+                    None => continue,
+                }
+            }
+        };
+        if page.exists() && !pages.contains(&page) {
+            pages.push(page);
+        }
+    }
+    if pages.is_empty() {
+        pages.push(out.join("index.html"));
+    }
+
+    for page in pages {
+        println!("Opening {}", page.display());
+        let mut cmd = if cfg!(windows) {
+            let mut cmd = Command::new("cmd");
+            cmd.arg("/C").arg("start").arg("");
+            cmd
+        } else if cfg!(target_os = "macos") {
+            Command::new("open")
+        } else {
+            Command::new("xdg-open")
+        };
+        cmd.arg(&page);
+        if !build.try_run_quiet(&mut cmd) {
+            println!("failed to open a browser, the documentation is at {}",
+                     page.display());
+        }
+    }
+}
+
 /// Generates the HTML rendered error-index by running the
 /// `error_index_generator` tool.
 pub fn error_index(build: &Build, target: &str) {
@@ -0,0 +1,285 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Implementation of `./x.py doctor`.
+//!
+//! Unlike the sanity checks, which stop the build at the first required tool
+//! that's missing, this looks for everything in the environment which commonly
+//! makes builds fail or slow, and explains how to fix each problem it finds.
+//! Nothing is built or modified, apart from a scratch file in `build/tmp`.
+
+use std::env;
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::path::Path;
+use std::process::{self, Command};
+
+use gcc;
+
+use Build;
+use sanity::Finder;
+
+/// The disk space a full build needs, in GiB.
+const REQUIRED_SPACE_GB: u64 = 20;
+
+/// The CMake version required by LLVM.
+const REQUIRED_CMAKE: (u32, u32, u32) = (3, 4, 3);
+
+/// The problems found, each with how to fix it.
+struct Report {
+    problems: usize,
+}
+
+impl Report {
+    fn ok(&self, what: &str) {
+        println!("ok:      {}", what);
+    }
+
+    fn note(&self, what: &str) {
+        println!("note:    {}", what);
+    }
+
+    fn problem(&mut self, what: &str, fix: &str) {
+        self.problems += 1;
+        println!("problem: {}", what);
+        for line in fix.lines() {
+            println!("         {}", line);
+        }
+    }
+}
+
+pub fn doctor(build: &Build) {
+    let mut report = Report { problems: 0 };
+    let mut finder = Finder::new();
+
+    check_disk_space(build, &mut report);
+    check_path_length(build, &mut report);
+    check_case_sensitivity(build, &mut report);
+    check_submodules(build, &mut report, &mut finder);
+    check_python(build, &mut report, &mut finder);
+    check_cmake(build, &mut report, &mut finder);
+    check_msvc(build, &mut report, &mut finder);
+    check_stale_downloads(build, &mut report);
+
+    if report.problems == 0 {
+        println!("\nno problems found");
+    } else {
+        println!("\n{} problem(s) found", report.problems);
+        process::exit(1);
+    }
+}
+
+fn check_disk_space(build: &Build, report: &mut Report) {
+    if cfg!(windows) {
+        return report.note(&format!("the free disk space isn't checked on Windows, \
+                                     a full build needs about {} GiB",
+                                    REQUIRED_SPACE_GB));
+    }
+    let dir = if build.out.exists() { &build.out } else { &build.src };
+    let out = match Command::new("df").arg("-Pk").arg(dir).output() {
+        Ok(ref out) if out.status.success() => String::from_utf8_lossy(&out.stdout).into_owned(),
+        _ => return report.note("couldn't determine the free disk space with `df`"),
+    };
+    // The second line is `<filesystem> <size> <used> <available> ...`.
+    let available = out.lines().nth(1)
+        .and_then(|line| line.split_whitespace().nth(3))
+        .and_then(|kb| kb.parse::<u64>().ok());
+    match available {
+        Some(kb) if kb / 1024 / 1024 < REQUIRED_SPACE_GB => {
+            report.problem(&format!("only {} GiB of disk space are available in {}",
+                                    kb / 1024 / 1024, dir.display()),
+                           &format!("a full build needs about {} GiB, free up some space or \
+                                     point `build.build-dir` elsewhere",
+                                    REQUIRED_SPACE_GB))
+        }
+        Some(kb) => report.ok(&format!("{} GiB of disk space available", kb / 1024 / 1024)),
+        None => report.note("couldn't determine the free disk space with `df`"),
+    }
+}
+
+fn check_path_length(build: &Build, report: &mut Report) {
+    if !cfg!(windows) {
+        return
+    }
+    // The deepest paths below the build directory are about 160 characters
+    // long, which must fit into MAX_PATH (260 characters).
+    let len = build.out.to_string_lossy().len();
+    if len > 90 {
+        report.problem(&format!("the build directory {} is {} characters long",
+                                build.out.display(), len),
+                       "paths inside of it will exceed the Windows path length limit of 260\n\
+                        characters, move the checkout closer to the root of the drive")
+    } else {
+        report.ok("the build directory's path is short enough for Windows");
+    }
+}
+
+fn check_case_sensitivity(build: &Build, report: &mut Report) {
+    let dir = build.out.join("tmp");
+    if fs::create_dir_all(&dir).is_err() {
+        return report.note(&format!("couldn't create {}", dir.display()));
+    }
+    let upper = dir.join("DOCTOR-CASE");
+    let lower = dir.join("doctor-case");
+    if File::create(&upper).is_err() {
+        return report.note(&format!("couldn't create {}", upper.display()));
+    }
+    if lower.exists() {
+        report.note("the file system of the build directory is case-insensitive, which is \
+                     supported but will make the few tests whose file names differ only in \
+                     case interfere with each other");
+    } else {
+        report.ok("the file system of the build directory is case-sensitive");
+    }
+    let _ = fs::remove_file(&upper);
+}
+
+fn check_submodules(build: &Build, report: &mut Report, finder: &mut Finder) {
+    if !build.rust_info.is_git() {
+        return
+    }
+    if finder.maybe_have("git").is_none() {
+        return report.problem("git isn't installed, but the source is a git checkout",
+                              "install git, which is needed to update the submodules");
+    }
+    if !build.config.submodules {
+        return
+    }
+    let missing = ["src/llvm", "src/jemalloc", "src/liblibc", "src/doc/book"].iter()
+        .filter(|dir| {
+            fs::read_dir(build.src.join(dir)).map(|mut d| d.next().is_none()).unwrap_or(true)
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+    if missing.is_empty() {
+        report.ok("the git submodules are checked out");
+    } else {
+        report.problem(&format!("git submodules aren't checked out: {}", missing.join(", ")),
+                       "rustbuild normally updates them itself, if that fails run\n\
+                        `git submodule update --init --recursive`");
+    }
+}
+
+fn check_python(build: &Build, report: &mut Report, finder: &mut Finder) {
+    let python = build.config.python.clone()
+        .or_else(|| env::var_os("BOOTSTRAP_PYTHON").map(Into::into))
+        .or_else(|| finder.maybe_have("python2.7"))
+        .or_else(|| finder.maybe_have("python2"))
+        .or_else(|| finder.maybe_have("python"));
+    let python = match python {
+        Some(python) => python,
+        None => return report.problem("python isn't installed", "install Python 2.7"),
+    };
+    let version = version(&python);
+    if version.starts_with("Python 2.7") {
+        report.ok(&format!("{} is {}", python.display(), version));
+    } else {
+        report.problem(&format!("{} is {}", python.display(), version),
+                       "some of the build and test scripts require Python 2.7, install it\n\
+                        and set `build.python` in config.toml if it isn't found")
+    }
+}
+
+fn check_cmake(build: &Build, report: &mut Report, finder: &mut Finder) {
+    let building_llvm = build.config.host.iter()
+        .filter_map(|host| build.config.target_config.get(host))
+        .any(|config| config.llvm_config.is_none());
+    if !building_llvm && !build.config.sanitizers {
+        return
+    }
+    let cmake = match finder.maybe_have("cmake") {
+        Some(cmake) => cmake,
+        None => {
+            return report.problem("cmake isn't installed, but is needed to build LLVM",
+                                  &format!("install CMake {}.{}.{} or newer",
+                                           REQUIRED_CMAKE.0, REQUIRED_CMAKE.1,
+                                           REQUIRED_CMAKE.2))
+        }
+    };
+    let version = version(&cmake);
+    let parsed = version.trim_left_matches("cmake version ")
+        .split(|c: char| c == '.' || c == '-')
+        .map(|n| n.parse::<u32>().unwrap_or(0))
+        .collect::<Vec<_>>();
+    let parsed = (parsed.get(0).cloned().unwrap_or(0),
+                  parsed.get(1).cloned().unwrap_or(0),
+                  parsed.get(2).cloned().unwrap_or(0));
+    if parsed < REQUIRED_CMAKE {
+        report.problem(&format!("{} is {}", cmake.display(), version),
+                       &format!("LLVM needs CMake {}.{}.{} or newer",
+                                REQUIRED_CMAKE.0, REQUIRED_CMAKE.1, REQUIRED_CMAKE.2))
+    } else {
+        report.ok(&format!("{} is {}", cmake.display(), version));
+    }
+
+    if build.config.ninja {
+        if finder.maybe_have("ninja").is_some() || finder.maybe_have("ninja-build").is_some() {
+            report.ok("ninja is installed");
+        } else {
+            report.problem("ninja isn't installed, but `llvm.ninja` is enabled",
+                           "install ninja, or disable `llvm.ninja` in config.toml");
+        }
+    }
+}
+
+fn check_msvc(build: &Build, report: &mut Report, finder: &mut Finder) {
+    for target in build.config.target.iter().filter(|t| t.contains("msvc")) {
+        let found = finder.maybe_have("cl.exe").is_some() ||
+                    gcc::windows_registry::find(target, "cl.exe").is_some();
+        if found {
+            report.ok(&format!("the MSVC compiler for {} is installed", target));
+        } else {
+            report.problem(&format!("the MSVC compiler for {} isn't installed", target),
+                           "install the Visual C++ build tools of Visual Studio 2015 or\n\
+                            newer, including the Windows SDK, or run the build from a\n\
+                            Developer Command Prompt");
+        }
+    }
+}
+
+fn check_stale_downloads(build: &Build, report: &mut Report) {
+    let date = build.src.join("src/stage0.txt");
+    let mut contents = String::new();
+    if File::open(&date).and_then(|mut f| f.read_to_string(&mut contents)).is_err() {
+        return
+    }
+    let date = match contents.lines().find(|l| l.starts_with("date:")) {
+        Some(line) => line.trim_left_matches("date:").trim(),
+        None => return,
+    };
+    let cache = build.out.join("cache");
+    let stale = fs::read_dir(&cache).into_iter().flat_map(|dir| dir)
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .filter(|name| name.starts_with("20") && name != date)
+        .collect::<Vec<_>>();
+    if stale.is_empty() {
+        return
+    }
+    report.note(&format!("{} holds stage0 downloads of outdated snapshots ({}), which \
+                          can be deleted",
+                         cache.display(), stale.join(", ")));
+}
+
+/// Returns the first line `tool --version` prints to stdout or stderr.
+fn version(tool: &Path) -> String {
+    match Command::new(tool).arg("--version").output() {
+        Ok(out) => {
+            let stdout = String::from_utf8_lossy(&out.stdout);
+            let stderr = String::from_utf8_lossy(&out.stderr);
+            stdout.lines().chain(stderr.lines())
+                  .map(|l| l.trim())
+                  .find(|l| !l.is_empty())
+                  .unwrap_or("an unknown version")
+                  .to_string()
+        }
+        Err(e) => format!("not executable ({})", e),
+    }
+}
@@ -22,6 +22,7 @@ use getopts::Options;
 
 use Build;
 use config::Config;
+use install;
 use metadata;
 use step;
 
@@ -31,10 +32,19 @@ pub struct Flags {
     pub on_fail: Option<String>,
     pub stage: Option<u32>,
     pub keep_stage: Option<u32>,
+    pub keep_stage_std: Option<u32>,
+    pub keep_stage_rustc: Option<u32>,
+    pub explain: bool,
+    pub timings: bool,
+    pub emit_compile_commands: bool,
+    pub hermetic: bool,
+    pub graph: Option<PathBuf>,
     pub build: String,
     pub host: Vec<String>,
     pub target: Vec<String>,
     pub config: Option<PathBuf>,
+    pub profile: Option<String>,
+    pub installed_rustc: Option<PathBuf>,
     pub src: PathBuf,
     pub jobs: Option<u32>,
     pub cmd: Subcommand,
@@ -47,23 +57,40 @@ pub enum Subcommand {
     },
     Doc {
         paths: Vec<PathBuf>,
+        open: bool,
     },
     Test {
         paths: Vec<PathBuf>,
         test_args: Vec<String>,
         fail_fast: bool,
+        force_rerun: bool,
+        test_shard: Option<(usize, usize)>,
+        parallel_suites: Option<usize>,
+        error_code: Option<String>,
+        retry: u32,
+        flaky_exit_code: Option<i32>,
     },
     Bench {
         paths: Vec<PathBuf>,
         test_args: Vec<String>,
     },
-    Clean,
+    Clean {
+        paths: Vec<PathBuf>,
+    },
     Dist {
         paths: Vec<PathBuf>,
     },
     Install {
         paths: Vec<PathBuf>,
+        components: Vec<String>,
+        destdir: Option<PathBuf>,
+    },
+    Run {
+        paths: Vec<PathBuf>,
+        args: Vec<String>,
     },
+    Stats,
+    Doctor,
 }
 
 impl Flags {
@@ -80,6 +107,9 @@ Subcommands:
     clean       Clean out build directories
     dist        Build distribution artifacts
     install     Install distribution artifacts
+    run         Build an in-tree tool if needed and run it
+    stats       Summarize the recorded statistics of recent builds
+    doctor      Diagnose common problems with the build environment
 
 To learn more about a subcommand, run `./x.py <subcommand> -h`");
 
@@ -88,13 +118,32 @@ To learn more about a subcommand, run `./x.py <subcommand> -h`");
         opts.optflagmulti("v", "verbose", "use verbose output (-vv for very verbose)");
         opts.optflag("i", "incremental", "use incremental compilation");
         opts.optopt("", "config", "TOML configuration file for build", "FILE");
+        opts.optopt("", "profile", "configuration profile to use as the defaults \
+                                    (compiler, library, dist or ci)", "PROFILE");
         opts.optopt("", "build", "build target of the stage0 compiler", "BUILD");
         opts.optmulti("", "host", "host targets to build", "HOST");
         opts.optmulti("", "target", "target targets to build", "TARGET");
         opts.optopt("", "on-fail", "command to run on failure", "CMD");
         opts.optopt("", "stage", "stage to build", "N");
         opts.optopt("", "keep-stage", "stage to keep without recompiling", "N");
+        opts.optopt("", "keep-stage-std", "don't recompile the std built by the stage N \
+                                           compiler or earlier ones", "N");
+        opts.optopt("", "keep-stage-rustc", "don't recompile the compiler built by the \
+                                             stage N compiler or earlier ones", "N");
+        opts.optflag("", "timings", "write a report of how long each step took to \
+                                     build/timings.html and build/timings.json");
+        opts.optflag("", "emit-compile-commands", "describe all in-tree crates in \
+                                                   build/rust-project.json for IDEs, \
+                                                   without building anything");
+        opts.optflag("", "hermetic", "only use the tools and downloads pinned by the \
+                                      hermetic lockfile, and write a build manifest");
+        opts.optflag("", "explain", "print the steps that would be executed and why, \
+                                     without executing them");
+        opts.optopt("", "graph", "with --explain, also write the step graph in the \
+                                  DOT format to FILE", "FILE");
         opts.optopt("", "src", "path to the root of the rust checkout", "DIR");
+        opts.optopt("", "with-installed-rustc", "build only the standard library, using \
+                                                 the rustc installed in DIR", "DIR");
         opts.optopt("j", "jobs", "number of jobs to run in parallel", "JOBS");
         opts.optflag("h", "help", "print this help message");
 
@@ -119,7 +168,10 @@ To learn more about a subcommand, run `./x.py <subcommand> -h`");
             || (s == "doc")
             || (s == "clean")
             || (s == "dist")
-            || (s == "install"));
+            || (s == "install")
+            || (s == "run")
+            || (s == "stats")
+            || (s == "doctor"));
         let subcommand = match subcommand {
             Some(s) => s,
             None => {
@@ -129,13 +181,40 @@ To learn more about a subcommand, run `./x.py <subcommand> -h`");
             }
         };
 
+        // The arguments after `--` are passed to the tool run by `run`, rather
+        // than parsed as paths.
+        let (args, tool_args) = match args.iter().position(|a| a == "--") {
+            Some(i) if subcommand == "run" => (&args[..i], args[i + 1..].to_vec()),
+            _ => (args, Vec::new()),
+        };
+
         // Some subcommands get extra options
         match subcommand.as_str() {
             "test"  => {
                 opts.optflag("", "no-fail-fast", "Run all tests regardless of failure");
+                opts.optflag("", "force-rerun", "Rerun tests even if their inputs are unchanged");
+                opts.optopt("", "test-shard", "split each compiletest suite into N shards and \
+                                              only run the Kth one", "K/N");
+                opts.optopt("", "parallel-suites", "run up to N compiletest suites at once, \
+                                                   prefixing their output", "N");
+                opts.optopt("", "error-code", "only run the compiletest tests which expect \
+                                               the error with this code, e.g. E0277", "CODE");
                 opts.optmulti("", "test-args", "extra arguments", "ARGS");
+                opts.optopt("", "retry", "rerun failing test suites up to N times, reporting \
+                                         those which then pass as flaky", "N");
+                opts.optopt("", "flaky-exit-code", "exit with CODE if some test suites only \
+                                                   passed when retried", "CODE");
             },
             "bench" => { opts.optmulti("", "test-args", "extra arguments", "ARGS"); },
+            "doc" => {
+                opts.optflag("", "open", "open the generated documentation in a browser");
+            },
+            "install" => {
+                opts.optmulti("", "component", "only install the given components, \
+                                                optionally for a single target", "NAME");
+                opts.optopt("", "destdir", "stage the installation in DIR, \
+                                            overriding $DESTDIR", "DIR");
+            },
             _ => { },
         };
 
@@ -204,6 +283,39 @@ Arguments:
         ./x.py test src/libstd --test-args hash_map
         ./x.py test src/libstd --stage 0
 
+    Test suites which previously passed are skipped if none of their inputs
+    have changed since. Pass --force-rerun to run them anyway:
+
+        ./x.py test src/test/run-pass --force-rerun
+
+    Test suites which fail can be rerun with --retry. Suites which then pass
+    are reported as flaky and don't fail the build, unless --flaky-exit-code
+    is given:
+
+        ./x.py test src/test/run-pass --no-fail-fast --retry 2
+
+    Compiletest suites can be split into shards, for example to distribute
+    them across several machines. This runs the second of four shards:
+
+        ./x.py test src/test/run-pass --test-shard 2/4
+
+    Tests other than the compiletest suites are only run by the first shard.
+    With `build.test-workers` set in config.toml, the shards are run on those
+    workers automatically.
+
+    Independent compiletest suites can be run concurrently, in the
+    background while the rest of the tests run. Each line of their output
+    is prefixed with the suite, and their failures are reported at the end:
+
+        ./x.py test src/test/run-pass src/test/compile-fail --parallel-suites 2
+
+    Diagnostics work can be checked by only running the tests which expect
+    a given error code, in an annotation or in the expected output of a UI
+    test. Without paths, the ui, compile-fail and parse-fail suites are run:
+
+        ./x.py test --error-code E0277
+        ./x.py test src/test/ui --error-code E0277
+
     If no arguments are passed then the complete artifacts for that stage are
     compiled and tested.
 
@@ -223,7 +335,76 @@ Arguments:
     If no arguments are passed then everything is documented:
 
         ./x.py doc
-        ./x.py doc --stage 1");
+        ./x.py doc --stage 1
+
+    Passing a crate of the standard library or of the compiler other than
+    src/libstd and src/rustc documents only that crate, reusing the
+    documentation of its dependencies from previous runs. With --open the
+    result is opened in a web browser:
+
+        ./x.py doc src/libcore --open");
+            }
+            "clean" => {
+                subcommand_help.push_str("\n
+Arguments:
+    Without arguments, everything but the download cache and LLVM is
+    removed. The output of a single stage, and of only some parts of the
+    build, can be removed instead by passing --stage and/or paths:
+
+        ./x.py clean --stage 1
+        ./x.py clean --stage 1 src/libstd
+        ./x.py clean src/librustc src/tools
+        ./x.py clean src/llvm
+
+    The parts which can be cleaned are src/libstd, src/libtest,
+    src/librustc, src/tools and src/llvm.");
+            }
+            "install" => {
+                subcommand_help.push_str("\n
+Arguments:
+    This subcommand accepts a number of paths to the artifacts to install,
+    or alternatively the names of the components to install. A component
+    name can be suffixed with a target to only install it for that target:
+
+        ./x.py install src/libstd src/librustc
+        ./x.py install --component rustc,rust-std-x86_64-unknown-linux-gnu
+
+    Known components are rustc, rust-std, rust-docs, cargo, rls,
+    rust-analysis and rust-src.
+
+    The installation can be staged in a separate directory with --destdir
+    (or the DESTDIR environment variable). A list of all files installed
+    is written to build/install-manifest.txt:
+
+        ./x.py install --destdir /tmp/staging");
+            }
+            "run" => {
+                subcommand_help.push_str("\n
+Arguments:
+    This subcommand accepts the path of an in-tree tool, followed by `--`
+    and the arguments to run it with. The tool is built first if needed,
+    with the stage0 compiler, except for rustdoc which is the one of the
+    given stage. The tool is run with the environment it's run with during
+    the build, e.g. with the compiler libraries in its library path:
+
+        ./x.py run src/tools/tidy -- src
+        ./x.py run src/tools/error_index_generator -- html
+        ./x.py run src/librustdoc --stage 1 -- --version");
+            }
+            "stats" => {
+                subcommand_help.push_str("\n
+Arguments:
+    This subcommand accepts no arguments. It lists the most recent
+    invocations of rustbuild and how long each command line took on
+    average, as recorded with `build.stats = true` in config.toml.");
+            }
+            "doctor" => {
+                subcommand_help.push_str("\n
+Arguments:
+    This subcommand accepts no arguments. It checks the environment for
+    problems which commonly break or slow down the build, such as missing
+    or outdated tools, uninitialized submodules or too little disk space,
+    and explains how to fix them. It exits with an error if any are found.");
             }
             _ => { }
         };
@@ -242,7 +423,8 @@ Arguments:
         // All subcommands can have an optional "Available paths" section
         if matches.opt_present("verbose") {
             let flags = Flags::parse(&["build".to_string()]);
-            let mut config = Config::parse(&flags.build, cfg_file.clone());
+            let mut config = Config::parse(&flags.build, cfg_file.clone(),
+                                           matches.opt_str("profile"));
             config.build = flags.build.clone();
             let mut build = Build::new(flags, config);
             metadata::build(&mut build);
@@ -260,15 +442,60 @@ Arguments:
             usage(0, &opts, &subcommand_help, &extra_help);
         }
 
+        let installed_rustc = matches.opt_str("with-installed-rustc").map(|p| cwd.join(p));
+
         let cmd = match subcommand.as_str() {
             "build" => {
+                // Without any paths only the standard library can be built by
+                // an installed compiler, as the compiler itself needs stage0.
+                let paths = if installed_rustc.is_some() && paths.is_empty() {
+                    vec![cwd.join("src/libstd")]
+                } else {
+                    paths
+                };
                 Subcommand::Build { paths: paths }
             }
             "test" => {
+                let test_shard = matches.opt_str("test-shard").map(|s| {
+                    match parse_shard(&s) {
+                        Some(shard) => shard,
+                        None => {
+                            println!("\n--test-shard must be K/N with 1 <= K <= N, \
+                                      but found `{}`\n", s);
+                            usage(1, &opts, &subcommand_help, &extra_help);
+                        }
+                    }
+                });
+                let parallel_suites = matches.opt_str("parallel-suites").map(|s| {
+                    match s.parse::<usize>() {
+                        Ok(n) if n > 0 => n,
+                        _ => {
+                            println!("\n--parallel-suites must be a positive number, \
+                                      but found `{}`\n", s);
+                            usage(1, &opts, &subcommand_help, &extra_help);
+                        }
+                    }
+                });
+                let error_code = matches.opt_str("error-code");
+                // Only these suites have tests which expect errors.
+                let paths = if error_code.is_some() && paths.is_empty() {
+                    vec![cwd.join("src/test/ui"),
+                         cwd.join("src/test/compile-fail"),
+                         cwd.join("src/test/parse-fail")]
+                } else {
+                    paths
+                };
                 Subcommand::Test {
                     paths: paths,
                     test_args: matches.opt_strs("test-args"),
                     fail_fast: !matches.opt_present("no-fail-fast"),
+                    force_rerun: matches.opt_present("force-rerun"),
+                    test_shard: test_shard,
+                    parallel_suites: parallel_suites,
+                    error_code: error_code,
+                    retry: matches.opt_str("retry").map(|n| n.parse().unwrap()).unwrap_or(0),
+                    flaky_exit_code: matches.opt_str("flaky-exit-code")
+                                            .map(|c| c.parse().unwrap()),
                 }
             }
             "bench" => {
@@ -278,14 +505,13 @@ Arguments:
                 }
             }
             "doc" => {
-                Subcommand::Doc { paths: paths }
+                Subcommand::Doc {
+                    paths: paths,
+                    open: matches.opt_present("open"),
+                }
             }
             "clean" => {
-                if paths.len() > 0 {
-                    println!("\nclean takes no arguments\n");
-                    usage(1, &opts, &subcommand_help, &extra_help);
-                }
-                Subcommand::Clean
+                Subcommand::Clean { paths: paths }
             }
             "dist" => {
                 Subcommand::Dist {
@@ -293,10 +519,31 @@ Arguments:
                 }
             }
             "install" => {
+                let components = split(matches.opt_strs("component"));
+                for component in components.iter() {
+                    if install::parse_component(component).is_none() {
+                        println!("\nunknown component `{}`\n", component);
+                        usage(1, &opts, &subcommand_help, &extra_help);
+                    }
+                }
                 Subcommand::Install {
                     paths: paths,
+                    components: components,
+                    destdir: matches.opt_str("destdir").map(|p| cwd.join(p)),
+                }
+            }
+            "run" => {
+                if paths.len() != 1 {
+                    println!("\nexactly one tool must be given to run\n");
+                    usage(1, &opts, &subcommand_help, &extra_help);
+                }
+                Subcommand::Run {
+                    paths: paths,
+                    args: tool_args,
                 }
             }
+            "stats" => Subcommand::Stats,
+            "doctor" => Subcommand::Doctor,
             _ => {
                 usage(1, &opts, &subcommand_help, &extra_help);
             }
@@ -309,6 +556,16 @@ Arguments:
             stage = Some(1);
         }
 
+        if installed_rustc.is_some() {
+            match stage {
+                Some(0) | None => stage = Some(0),
+                Some(_) => {
+                    println!("\n--with-installed-rustc can only be used with --stage 0\n");
+                    usage(1, &opts, &subcommand_help, &extra_help);
+                }
+            }
+        }
+
         let cwd = t!(env::current_dir());
         let src = matches.opt_str("src").map(PathBuf::from)
             .or_else(|| env::var_os("SRC").map(PathBuf::from))
@@ -319,12 +576,21 @@ Arguments:
             stage: stage,
             on_fail: matches.opt_str("on-fail"),
             keep_stage: matches.opt_str("keep-stage").map(|j| j.parse().unwrap()),
+            keep_stage_std: matches.opt_str("keep-stage-std").map(|j| j.parse().unwrap()),
+            keep_stage_rustc: matches.opt_str("keep-stage-rustc").map(|j| j.parse().unwrap()),
+            explain: matches.opt_present("explain"),
+            timings: matches.opt_present("timings"),
+            emit_compile_commands: matches.opt_present("emit-compile-commands"),
+            hermetic: matches.opt_present("hermetic"),
+            graph: matches.opt_str("graph").map(PathBuf::from),
             build: matches.opt_str("build").unwrap_or_else(|| {
                 env::var("BUILD").unwrap()
             }),
             host: split(matches.opt_strs("host")),
             target: split(matches.opt_strs("target")),
             config: cfg_file,
+            profile: matches.opt_str("profile"),
+            installed_rustc: installed_rustc,
             src: src,
             jobs: matches.opt_str("jobs").map(|j| j.parse().unwrap()),
             cmd: cmd,
@@ -350,6 +616,57 @@ impl Subcommand {
             _ => false,
         }
     }
+
+    pub fn force_rerun(&self) -> bool {
+        match *self {
+            Subcommand::Test { force_rerun, .. } => force_rerun,
+            _ => false,
+        }
+    }
+
+    pub fn retry(&self) -> u32 {
+        match *self {
+            Subcommand::Test { retry, .. } => retry,
+            _ => 0,
+        }
+    }
+
+    pub fn flaky_exit_code(&self) -> Option<i32> {
+        match *self {
+            Subcommand::Test { flaky_exit_code, .. } => flaky_exit_code,
+            _ => None,
+        }
+    }
+
+    pub fn test_shard(&self) -> Option<(usize, usize)> {
+        match *self {
+            Subcommand::Test { test_shard, .. } => test_shard,
+            _ => None,
+        }
+    }
+
+    pub fn parallel_suites(&self) -> Option<usize> {
+        match *self {
+            Subcommand::Test { parallel_suites, .. } => parallel_suites,
+            _ => None,
+        }
+    }
+
+    pub fn error_code(&self) -> Option<&str> {
+        match *self {
+            Subcommand::Test { ref error_code, .. } => error_code.as_ref().map(|s| &s[..]),
+            _ => None,
+        }
+    }
+}
+
+/// Parses the `K/N` argument of `--test-shard`, with `1 <= K <= N`.
+fn parse_shard(s: &str) -> Option<(usize, usize)> {
+    let mut parts = s.splitn(2, '/').map(|p| p.parse::<usize>().ok());
+    match (parts.next(), parts.next()) {
+        (Some(Some(k)), Some(Some(n))) if 1 <= k && k <= n => Some((k, n)),
+        _ => None,
+    }
 }
 
 fn split(s: Vec<String>) -> Vec<String> {
@@ -0,0 +1,235 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Hermetic builds, enabled with `--hermetic` or `build.hermetic`.
+//!
+//! In a hermetic build all external inputs are pinned by a lockfile,
+//! `hermetic.lock` in the source directory unless `build.hermetic-lock` says
+//! otherwise. It consists of lines of the form
+//!
+//! ```text
+//! tool <name> <first line of `<tool> --version`>
+//! sha256 <file in build/cache> <hash>
+//! ```
+//!
+//! Every artifact bootstrap.py downloads into `build/cache` (the stage0
+//! compiler and cargo) must be listed with its hash; bootstrap.py checks this
+//! before unpacking it. Then, before anything is built, every tool the build
+//! uses must be listed with exactly the version found. When the tools don't
+//! match, what was found, including the hashes of the downloads, is written to
+//! `build/hermetic.lock.found` so it can be reviewed and adopted.
+//!
+//! After a successful build `build/hermetic-manifest.txt` records the commit,
+//! the locked inputs and the hashes of all distribution artifacts, so that two
+//! builds can be compared for reproducibility.
+
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use build_helper::output;
+
+use Build;
+
+/// The external inputs of a build, in lockfile order.
+struct Inputs {
+    tools: Vec<(String, String)>,
+    artifacts: Vec<(String, String)>,
+}
+
+/// Verifies that the tools used by the build are exactly those pinned by the
+/// lockfile, panicking otherwise.
+///
+/// The downloaded artifacts have already been verified by bootstrap.py.
+pub fn check(build: &Build) {
+    let lockfile = lockfile(build);
+    let found = inputs(build);
+
+    let mut contents = String::new();
+    let errors = match File::open(&lockfile) {
+        Ok(mut f) => {
+            t!(f.read_to_string(&mut contents));
+            compare(&found, &contents)
+        }
+        Err(_) => vec![format!("lockfile {} does not exist", lockfile.display())],
+    };
+    if errors.is_empty() {
+        return
+    }
+
+    let path = build.out.join("hermetic.lock.found");
+    t!(t!(File::create(&path)).write_all(render(&found).as_bytes()));
+    let mut msg = String::from("\n\nthe inputs of this build don't match the hermetic lockfile:\n");
+    for error in errors {
+        msg.push_str(&format!("    {}\n", error));
+    }
+    msg.push_str(&format!("\nthe inputs which were found have been written to {}, \
+                           review them and copy them to {} to use them\n\n",
+                          path.display(), lockfile.display()));
+    panic!(msg);
+}
+
+/// Writes `build/hermetic-manifest.txt` describing the inputs and outputs of
+/// the build.
+pub fn write_manifest(build: &Build) {
+    let mut manifest = String::new();
+    if let Some(sha) = build.rust_info.sha() {
+        manifest.push_str(&format!("commit {}\n", sha));
+    }
+    manifest.push_str(&render(&inputs(build)));
+
+    let mut outputs = Vec::new();
+    files(&build.out.join("dist"), &mut outputs);
+    outputs.sort();
+    for path in outputs {
+        let name = path.strip_prefix(&build.out).unwrap();
+        manifest.push_str(&format!("output {} {}\n", name.display(), sha256(build, &path)));
+    }
+
+    let path = build.out.join("hermetic-manifest.txt");
+    t!(t!(File::create(&path)).write_all(manifest.as_bytes()));
+    println!("hermetic build manifest written to {}", path.display());
+}
+
+fn lockfile(build: &Build) -> PathBuf {
+    build.config.hermetic_lock.clone().unwrap_or_else(|| build.src.join("hermetic.lock"))
+}
+
+/// Collects the versions of the tools and hashes of the downloaded artifacts
+/// this build uses.
+fn inputs(build: &Build) -> Inputs {
+    let mut tools = Vec::new();
+    tools.push(("rustc".to_string(), version(&build.initial_rustc)));
+    tools.push(("cargo".to_string(), version(&build.initial_cargo)));
+    for target in build.config.target.iter() {
+        if target.contains("emscripten") {
+            continue
+        }
+        tools.push((format!("cc-{}", target), version(build.cc(target))));
+    }
+    for host in build.config.host.iter() {
+        if let Ok(cxx) = build.cxx(host) {
+            tools.push((format!("cxx-{}", host), version(cxx)));
+        }
+    }
+    let building_llvm = build.config.host.iter()
+        .filter_map(|host| build.config.target_config.get(host))
+        .any(|config| config.llvm_config.is_none());
+    if building_llvm || build.config.sanitizers {
+        tools.push(("cmake".to_string(), version(Path::new("cmake"))));
+    }
+    if let Some(ref python) = build.config.python {
+        tools.push(("python".to_string(), version(python)));
+    }
+
+    // Everything below `build/cache` except for the LLVM cache has been
+    // downloaded by bootstrap.py.
+    let mut downloads = Vec::new();
+    let cache = build.out.join("cache");
+    for entry in fs::read_dir(&cache).into_iter().flat_map(|dir| dir) {
+        let path = t!(entry).path();
+        if path.file_name().and_then(|s| s.to_str()) != Some("llvm") {
+            files(&path, &mut downloads);
+        }
+    }
+    downloads.sort();
+    let artifacts = downloads.iter().map(|path| {
+        let name = path.strip_prefix(&cache).unwrap();
+        (name.to_str().unwrap().replace("\\", "/"), sha256(build, path))
+    }).collect();
+
+    Inputs { tools: tools, artifacts: artifacts }
+}
+
+/// Returns a description of every difference between the tools `found` and
+/// those in the lockfile `contents`.
+fn compare(found: &Inputs, contents: &str) -> Vec<String> {
+    let mut tools = Vec::new();
+    for line in contents.lines().map(|l| l.trim()) {
+        if line.is_empty() || line.starts_with("#") {
+            continue
+        }
+        let mut parts = line.splitn(3, ' ');
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some("tool"), Some(name), Some(version)) => tools.push((name, version)),
+            (Some("sha256"), Some(_), Some(_)) => {}
+            _ => panic!("invalid line in hermetic lockfile: {}", line),
+        }
+    }
+
+    let mut errors = Vec::new();
+    for &(ref name, ref version) in found.tools.iter() {
+        match tools.iter().find(|&&(n, _)| n == name) {
+            Some(&(_, v)) if v == version => {}
+            Some(&(_, v)) => {
+                errors.push(format!("{} is `{}`, but `{}` is required", name, version, v))
+            }
+            None => errors.push(format!("{} (`{}`) is not in the lockfile", name, version)),
+        }
+    }
+    errors
+}
+
+fn render(inputs: &Inputs) -> String {
+    let mut ret = String::new();
+    for &(ref name, ref version) in inputs.tools.iter() {
+        ret.push_str(&format!("tool {} {}\n", name, version));
+    }
+    for &(ref name, ref hash) in inputs.artifacts.iter() {
+        ret.push_str(&format!("sha256 {} {}\n", name, hash));
+    }
+    ret
+}
+
+/// Returns the first line `tool --version` prints.
+///
+/// Some tools, like MSVC's `cl.exe`, don't understand `--version` but print
+/// their version to stderr anyway, so the exit status is ignored.
+fn version(tool: &Path) -> String {
+    let out = match Command::new(tool).arg("--version").output() {
+        Ok(out) => out,
+        Err(e) => panic!("failed to execute {}: {}", tool.display(), e),
+    };
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    stdout.lines().chain(stderr.lines())
+          .map(|l| l.trim())
+          .find(|l| !l.is_empty())
+          .unwrap_or("")
+          .to_string()
+}
+
+fn sha256(build: &Build, path: &Path) -> String {
+    let mut cmd = if build.build.contains("apple") {
+        let mut cmd = Command::new("shasum");
+        cmd.arg("-a").arg("256");
+        cmd
+    } else {
+        Command::new("sha256sum")
+    };
+    let out = output(cmd.arg(path));
+    out.split_whitespace().next().unwrap().to_string()
+}
+
+/// Collects all files below `path`, or `path` itself if it's a file.
+fn files(path: &Path, dst: &mut Vec<PathBuf>) {
+    let meta = match fs::metadata(path) {
+        Ok(meta) => meta,
+        Err(_) => return,
+    };
+    if meta.is_dir() {
+        for entry in t!(fs::read_dir(path)) {
+            files(&t!(entry).path(), dst);
+        }
+    } else {
+        dst.push(path.to_path_buf());
+    }
+}
@@ -13,13 +13,42 @@
 //! This module is responsible for installing the standard library,
 //! compiler, and documentation.
 
+use std::collections::BTreeSet;
 use std::env;
-use std::fs;
+use std::fs::{self, File};
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf, Component};
 use std::process::Command;
 
 use Build;
 use dist::{pkgname, sanitize_sh, tmpdir};
+use flags::Subcommand;
+
+/// Names of the installable components and the rules installing them.
+const COMPONENTS: &'static [(&'static str, &'static str)] = &[
+    ("rustc", "install-rustc"),
+    ("rust-std", "install-std"),
+    ("rust-docs", "install-docs"),
+    ("cargo", "install-cargo"),
+    ("rls", "install-rls"),
+    ("rust-analysis", "install-analysis"),
+    ("rust-src", "install-src"),
+];
+
+/// Parses a component name passed to `--component`, like `rust-std` or
+/// `rust-std-x86_64-unknown-linux-gnu`, into the name of its install rule
+/// and the target it was restricted to, if any.
+pub fn parse_component(component: &str) -> Option<(&'static str, Option<&str>)> {
+    for &(name, rule) in COMPONENTS {
+        if component == name {
+            return Some((rule, None))
+        }
+        if component.starts_with(name) && component[name.len()..].starts_with("-") {
+            return Some((rule, Some(&component[name.len() + 1..])))
+        }
+    }
+    None
+}
 
 pub struct Installer<'a> {
     build: &'a Build,
@@ -29,6 +58,7 @@ pub struct Installer<'a> {
     bindir: PathBuf,
     libdir: PathBuf,
     mandir: PathBuf,
+    destdir: Option<PathBuf>,
     empty_dir: PathBuf,
 }
 
@@ -59,7 +89,11 @@ impl<'a> Installer<'a> {
         let libdir = prefix.join(libdir);
         let mandir = prefix.join(mandir);
 
-        let destdir = env::var_os("DESTDIR").map(PathBuf::from);
+        let destdir = match build.flags.cmd {
+            Subcommand::Install { ref destdir, .. } => destdir.clone(),
+            _ => None,
+        };
+        let destdir = destdir.or_else(|| env::var_os("DESTDIR").map(PathBuf::from));
 
         let prefix = add_destdir(&prefix, &destdir);
         let sysconfdir = add_destdir(&sysconfdir, &destdir);
@@ -80,6 +114,7 @@ impl<'a> Installer<'a> {
             bindir,
             libdir,
             mandir,
+            destdir,
             empty_dir,
         }
     }
@@ -88,10 +123,8 @@ impl<'a> Installer<'a> {
         self.install_sh("docs", "rust-docs", stage, Some(host));
     }
 
-    pub fn install_std(&self, stage: u32) {
-        for target in self.build.config.target.iter() {
-            self.install_sh("std", "rust-std", stage, Some(target));
-        }
+    pub fn install_std(&self, stage: u32, target: &str) {
+        self.install_sh("std", "rust-std", stage, Some(target));
     }
 
     pub fn install_cargo(&self, stage: u32, host: &str) {
@@ -132,6 +165,61 @@ impl<'a> Installer<'a> {
            .arg(format!("--mandir={}", sanitize_sh(&self.mandir)))
            .arg("--disable-ldconfig");
         self.build.run(&mut cmd);
+        self.write_manifest();
+    }
+
+    /// Writes the list of all files installed under the prefix, as recorded
+    /// in the manifests of the installed components, to
+    /// `build/install-manifest.txt`.
+    ///
+    /// The paths are those of the final installation, without the destdir,
+    /// so the list can be used by packagers to remove the files again.
+    fn write_manifest(&self) {
+        let rustlib = self.libdir.join("rustlib");
+        let mut entries = BTreeSet::new();
+        for name in ["components", "rust-installer-version", "uninstall.sh"].iter() {
+            let path = rustlib.join(name);
+            if path.exists() {
+                entries.insert(format!("file:{}", self.strip_destdir(&path).display()));
+            }
+        }
+        for entry in t!(fs::read_dir(&rustlib)) {
+            let path = t!(entry).path();
+            let is_manifest = path.file_name().and_then(|n| n.to_str())
+                                  .map_or(false, |n| n.starts_with("manifest-"));
+            if !is_manifest {
+                continue
+            }
+            entries.insert(format!("file:{}", self.strip_destdir(&path).display()));
+            let mut contents = String::new();
+            t!(t!(File::open(&path)).read_to_string(&mut contents));
+            for line in contents.lines() {
+                let mut parts = line.splitn(2, ':');
+                match (parts.next(), parts.next()) {
+                    (Some(kind), Some(path)) => {
+                        let path = self.strip_destdir(Path::new(path));
+                        entries.insert(format!("{}:{}", kind, path.display()));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let mut manifest = t!(File::create(self.build.out.join("install-manifest.txt")));
+        for entry in entries {
+            t!(writeln!(manifest, "{}", entry));
+        }
+    }
+
+    fn strip_destdir(&self, path: &Path) -> PathBuf {
+        let destdir = match self.destdir {
+            Some(ref destdir) => destdir,
+            None => return path.to_path_buf(),
+        };
+        match path.strip_prefix(destdir) {
+            Ok(path) => Path::new("/").join(path),
+            Err(_) => path.to_path_buf(),
+        }
     }
 }
 
@@ -79,7 +79,7 @@ extern crate toml;
 #[cfg(unix)]
 extern crate libc;
 
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
 use std::cmp;
 use std::collections::HashMap;
 use std::env;
@@ -91,7 +91,7 @@ use std::process::Command;
 
 use build_helper::{run_silent, run_suppressed, try_run_silent, try_run_suppressed, output, mtime};
 
-use util::{exe, libdir, add_lib_path, OutputFolder, CiEnv};
+use util::{exe, libdir, add_lib_path, push_exe_path, OutputFolder, CiEnv};
 
 mod cc;
 mod channel;
@@ -102,10 +102,15 @@ mod metadata;
 mod config;
 mod dist;
 mod doc;
+mod distribute;
+mod doctor;
 mod flags;
+mod hermetic;
 mod install;
 mod native;
+mod run;
 mod sanity;
+mod stats;
 mod step;
 pub mod util;
 
@@ -192,6 +197,10 @@ pub struct Build {
     is_sudo: bool,
     ci_env: CiEnv,
     delayed_failures: Cell<usize>,
+    flaky_runs: Cell<usize>,
+    cache_hits: Cell<usize>,
+    cache_misses: Cell<usize>,
+    suites: RefCell<check::Suites>,
 }
 
 #[derive(Debug)]
@@ -269,9 +278,15 @@ impl Build {
             config.target.clone()
         };
 
+        let (initial_rustc, initial_cargo) = match flags.installed_rustc {
+            Some(ref root) => (push_exe_path(root.clone(), &["bin", "rustc"]),
+                               push_exe_path(root.clone(), &["bin", "cargo"])),
+            None => (config.initial_rustc.clone(), config.initial_cargo.clone()),
+        };
+
         Build {
-            initial_rustc: config.initial_rustc.clone(),
-            initial_cargo: config.initial_cargo.clone(),
+            initial_rustc: initial_rustc,
+            initial_cargo: initial_cargo,
             local_rebuild: config.local_rebuild,
             fail_fast: flags.cmd.fail_fast(),
             verbosity: cmp::max(flags.verbose, config.verbose),
@@ -296,6 +311,10 @@ impl Build {
             is_sudo: is_sudo,
             ci_env: CiEnv::current(),
             delayed_failures: Cell::new(0),
+            flaky_runs: Cell::new(0),
+            cache_hits: Cell::new(0),
+            cache_misses: Cell::new(0),
+            suites: RefCell::new(check::Suites::default()),
         }
     }
 
@@ -311,14 +330,29 @@ impl Build {
             job::setup(self);
         }
 
-        if let Subcommand::Clean = self.flags.cmd {
-            return clean::clean(self);
+        if let Subcommand::Clean { ref paths } = self.flags.cmd {
+            return clean::clean(self, paths);
+        }
+        if let Subcommand::Stats = self.flags.cmd {
+            return stats::summarize(self);
+        }
+        if let Subcommand::Doctor = self.flags.cmd {
+            return doctor::doctor(self);
+        }
+        if let Subcommand::Test { .. } = self.flags.cmd {
+            // Workers are passed a shard to run, and run it themselves.
+            if !self.config.test_workers.is_empty() && self.flags.cmd.test_shard().is_none() {
+                return distribute::test(self);
+            }
         }
 
         self.verbose("finding compilers");
         cc::find(self);
         self.verbose("running sanity check");
         sanity::check(self);
+        if self.flags.hermetic || self.config.hermetic {
+            hermetic::check(self);
+        }
         // If local-rust is the same major.minor as the current version, then force a local-rebuild
         let local_version_verbose = output(
             Command::new(&self.initial_rustc).arg("--version").arg("--verbose"));
@@ -330,10 +364,33 @@ impl Build {
             self.verbose(&format!("auto-detected local-rebuild {}", local_release));
             self.local_rebuild = true;
         }
+        // Building the standard library with an installed compiler only works
+        // if that compiler is of the same version as the library.
+        if self.flags.installed_rustc.is_some() && !self.local_rebuild {
+            panic!("the installed rustc has version {} but version {} is being built, \
+                    so it can't be used with --with-installed-rustc",
+                   local_release, my_version);
+        }
         self.verbose("learning about cargo");
         metadata::build(self);
 
+        if self.flags.emit_compile_commands {
+            return metadata::emit_rust_project(self);
+        }
+
+        let launcher_stats = native::LauncherStats::read(self);
         step::run(self);
+        if let Some(stats) = launcher_stats {
+            stats.report(self);
+        }
+
+        if self.flags.hermetic || self.config.hermetic {
+            hermetic::write_manifest(self);
+        }
+
+        if let Subcommand::Doc { ref paths, open: true } = self.flags.cmd {
+            doc::open(self, paths);
+        }
     }
 
     /// Clear out `dir` if `input` is newer.
@@ -392,10 +449,9 @@ impl Build {
              .env("RUSTC", self.out.join("bootstrap/debug/rustc"))
              .env("RUSTC_REAL", self.compiler_path(compiler))
              .env("RUSTC_STAGE", stage.to_string())
-             .env("RUSTC_CODEGEN_UNITS",
-                  self.config.rust_codegen_units.to_string())
+             .env("RUSTC_CODEGEN_UNITS", self.codegen_units(mode).to_string())
              .env("RUSTC_DEBUG_ASSERTIONS",
-                  self.config.rust_debug_assertions.to_string())
+                  self.debug_assertions(compiler, mode).to_string())
              .env("RUSTC_SYSROOT", self.sysroot(compiler))
              .env("RUSTC_LIBDIR", self.rustc_libdir(compiler))
              .env("RUSTC_RPATH", self.config.rust_rpath.to_string())
@@ -636,6 +692,36 @@ impl Build {
         }
     }
 
+    /// Returns the number of codegen units to compile crates of `mode` with.
+    fn codegen_units(&self, mode: Mode) -> u32 {
+        let units = match mode {
+            Mode::Libstd | Mode::Libtest => self.config.rust_codegen_units_std,
+            Mode::Librustc => self.config.rust_codegen_units_rustc,
+            Mode::Tool => None,
+        };
+        units.unwrap_or(self.config.rust_codegen_units)
+    }
+
+    /// Returns whether crates of `mode` compiled by `compiler` are built with
+    /// debug assertions.
+    ///
+    /// The `debug-assertions-std` and `debug-assertions-rustc` settings take
+    /// precedence over the per-stage settings, which take precedence over
+    /// `debug-assertions`. A stage is that of the sysroot the crates end up
+    /// in, so the stage N compiler is built by the stage N-1 compiler while
+    /// its standard library is built by the stage N compiler itself.
+    fn debug_assertions(&self, compiler: &Compiler, mode: Mode) -> bool {
+        let (group, stage) = match mode {
+            Mode::Libstd | Mode::Libtest => {
+                (self.config.rust_debug_assertions_std, compiler.stage)
+            }
+            Mode::Librustc => (self.config.rust_debug_assertions_rustc, compiler.stage + 1),
+            Mode::Tool => (None, compiler.stage),
+        };
+        group.or_else(|| self.config.rust_debug_assertions_stage.get(&stage).cloned())
+             .unwrap_or(self.config.rust_debug_assertions)
+    }
+
     /// Get the directory for incremental by-products when using the
     /// given compiler.
     fn incremental_dir(&self, compiler: &Compiler) -> PathBuf {
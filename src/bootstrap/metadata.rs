@@ -8,12 +8,15 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use std::collections::HashMap;
+use std::cmp;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::Write;
 use std::process::Command;
 use std::path::PathBuf;
 
 use build_helper::output;
-use rustc_serialize::json;
+use rustc_serialize::json::{self, Json, ToJson};
 
 use {Build, Crate};
 
@@ -30,6 +33,14 @@ struct Package {
     version: String,
     source: Option<String>,
     manifest_path: String,
+    targets: Vec<Target>,
+}
+
+#[derive(RustcDecodable)]
+struct Target {
+    name: String,
+    kind: Vec<String>,
+    src_path: String,
 }
 
 #[derive(RustcDecodable)]
@@ -56,12 +67,7 @@ fn build_krate(build: &mut Build, krate: &str) {
     // of packages we're going to have to know what `-p` arguments to pass it
     // to know what crates to test. Here we run `cargo metadata` to learn about
     // the dependency graph and what `-p` arguments there are.
-    let mut cargo = Command::new(&build.initial_cargo);
-    cargo.arg("metadata")
-         .arg("--format-version").arg("1")
-         .arg("--manifest-path").arg(build.src.join(krate).join("Cargo.toml"));
-    let output = output(&mut cargo);
-    let output: Output = json::decode(&output).unwrap();
+    let output = metadata(build, krate);
     let mut id2name = HashMap::new();
     for package in output.packages {
         if package.source.is_none() {
@@ -97,3 +103,110 @@ fn build_krate(build: &mut Build, krate: &str) {
         }
     }
 }
+
+fn metadata(build: &Build, krate: &str) -> Output {
+    let mut cargo = Command::new(&build.initial_cargo);
+    cargo.arg("metadata")
+         .arg("--format-version").arg("1")
+         .arg("--manifest-path").arg(build.src.join(krate).join("Cargo.toml"));
+    let output = output(&mut cargo);
+    json::decode(&output).unwrap()
+}
+
+/// Writes `build/rust-project.json`, a description of all crates which make
+/// up the standard library, the compiler and their dependencies.
+///
+/// This is the format understood by rust-analyzer for projects not built by
+/// Cargo alone. Each crate lists its root module, its dependencies and the
+/// `--cfg` flags and environment variables that rustbuild passes when
+/// compiling it, so an IDE can index the tree the same way it's built.
+pub fn emit_rust_project(build: &Build) {
+    // In-tree crates are compiled with `--cfg stageN` where N is the stage of
+    // the compiler building them. Unless a stage 0 build was requested that's
+    // the stage1 compiler, as later stages are uplifted from it.
+    let stage = build.flags.stage.map_or(1, |s| cmp::min(s, 1));
+    let mut common_cfgs = vec![format!("stage{}", stage)];
+    if build.config.rust_debug_assertions {
+        common_cfgs.push("debug_assertions".to_string());
+    }
+    let features = [
+        ("std", build.std_features()),
+        ("test", build.std_features()),
+        ("rustc-main", build.rustc_features()),
+    ];
+
+    let mut packages = Vec::new();
+    let mut index = HashMap::new();
+    let mut deps = HashMap::new();
+    for krate in ["src/libstd", "src/libtest", "src/rustc"].iter() {
+        let output = metadata(build, krate);
+        for package in output.packages {
+            if index.contains_key(&package.id) {
+                continue
+            }
+            let lib = package.targets.iter().position(|t| {
+                t.kind.iter().any(|k| k.contains("lib") || k == "proc-macro") ||
+                    package.name == "rustc-main"
+            });
+            if let Some(lib) = lib {
+                index.insert(package.id.clone(), packages.len());
+                packages.push((package, lib));
+            }
+        }
+        for node in output.resolve.nodes {
+            deps.entry(node.id).or_insert(node.dependencies);
+        }
+    }
+
+    let crates = packages.iter().map(|&(ref package, lib)| {
+        let in_tree = package.source.is_none();
+        let mut cfgs = Vec::new();
+        if in_tree {
+            cfgs.extend(common_cfgs.iter().cloned());
+        }
+        for &(name, ref features) in features.iter() {
+            if name == package.name {
+                cfgs.extend(features.split_whitespace().map(|f| {
+                    format!("feature=\"{}\"", f)
+                }));
+            }
+        }
+
+        let krate_deps = deps.get(&package.id).map(|v| &v[..]).unwrap_or(&[]);
+        let krate_deps = krate_deps.iter().filter_map(|dep| {
+            index.get(dep).map(|&i| {
+                let (ref dep, lib) = packages[i];
+                let mut obj = BTreeMap::new();
+                obj.insert("crate".to_string(), i.to_json());
+                obj.insert("name".to_string(),
+                           dep.targets[lib].name.replace("-", "_").to_json());
+                Json::Object(obj)
+            })
+        }).collect::<Vec<_>>();
+
+        let mut env = BTreeMap::new();
+        if in_tree {
+            env.insert("CFG_RELEASE".to_string(), build.rust_release().to_json());
+            env.insert("CFG_RELEASE_CHANNEL".to_string(), build.config.channel.to_json());
+            env.insert("CFG_VERSION".to_string(), build.rust_version().to_json());
+        }
+
+        let mut obj = BTreeMap::new();
+        obj.insert("root_module".to_string(), package.targets[lib].src_path.to_json());
+        obj.insert("edition".to_string(), "2015".to_json());
+        obj.insert("deps".to_string(), Json::Array(krate_deps));
+        obj.insert("cfg".to_string(), cfgs.to_json());
+        obj.insert("env".to_string(), Json::Object(env));
+        obj.insert("is_workspace_member".to_string(), in_tree.to_json());
+        Json::Object(obj)
+    }).collect::<Vec<_>>();
+
+    let mut project = BTreeMap::new();
+    project.insert("crates".to_string(), Json::Array(crates));
+    let project = Json::Object(project);
+
+    let path = build.out.join("rust-project.json");
+    let mut file = t!(File::create(&path));
+    t!(write!(file, "{}", project.pretty()));
+    println!("wrote {} crates to {}", packages.len(), path.display());
+}
@@ -18,11 +18,13 @@
 //! LLVM and compiler-rt are essentially just wired up to everything else to
 //! ensure that they're always in place if needed.
 
+use std::collections::hash_map::DefaultHasher;
 use std::env;
 use std::ffi::OsString;
 use std::fs::{self, File};
+use std::hash::{Hash, Hasher};
 use std::io::{Read, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 use build_helper::output;
@@ -93,6 +95,22 @@ pub fn llvm(build: &Build, target: &str) {
 
     let assertions = if build.config.llvm_assertions {"ON"} else {"OFF"};
 
+    // If this exact build of LLVM has been cached, by this or another
+    // checkout, just copy it into place.
+    let cache = llvm_cache(build, target, &[
+        &rebuild_trigger_contents, profile, assertions, llvm_targets, llvm_exp_targets,
+    ]);
+    if let Some(ref cache) = cache {
+        if cache.join("llvm-finished-building").exists() {
+            println!("Using cached LLVM from {}", cache.display());
+            util::cp_r(cache, &out_dir);
+            t!(t!(File::create(&done_stamp)).write_all(rebuild_trigger_contents.as_bytes()));
+            build.cache_hits.set(build.cache_hits.get() + 1);
+            return
+        }
+        build.cache_misses.set(build.cache_misses.get() + 1);
+    }
+
     cfg.target(target)
        .host(&build.build)
        .out_dir(&out_dir)
@@ -170,13 +188,13 @@ pub fn llvm(build: &Build, target: &str) {
                    build.config.ccache.as_ref().unwrap())
               .env("SCCACHE_TARGET", target);
 
-        // If ccache is configured we inform the build a little differently hwo
-        // to invoke ccache while also invoking our compilers.
-        } else if let Some(ref ccache) = build.config.ccache {
-           cfg.define("CMAKE_C_COMPILER", ccache)
-              .define("CMAKE_C_COMPILER_ARG1", sanitize_cc(cc))
-              .define("CMAKE_CXX_COMPILER", ccache)
-              .define("CMAKE_CXX_COMPILER_ARG1", sanitize_cc(cxx));
+        // Otherwise a launcher like ccache is put in front of our compilers,
+        // which CMake supports since 3.4.
+        } else if let Some(ref launcher) = build.config.ccache {
+           cfg.define("CMAKE_C_COMPILER", sanitize_cc(cc))
+              .define("CMAKE_C_COMPILER_LAUNCHER", launcher)
+              .define("CMAKE_CXX_COMPILER", sanitize_cc(cxx))
+              .define("CMAKE_CXX_COMPILER_LAUNCHER", launcher);
         } else {
            cfg.define("CMAKE_C_COMPILER", sanitize_cc(cc))
               .define("CMAKE_CXX_COMPILER", sanitize_cc(cxx));
@@ -200,6 +218,149 @@ pub fn llvm(build: &Build, target: &str) {
     cfg.build();
 
     t!(t!(File::create(&done_stamp)).write_all(rebuild_trigger_contents.as_bytes()));
+
+    if let Some(ref cache) = cache {
+        save_llvm_cache(&out_dir, cache);
+    }
+}
+
+/// Returns the name of a compiler launcher given by its name or path, e.g.
+/// `sccache` for `/usr/local/bin/sccache`.
+pub fn launcher_name(launcher: &str) -> &str {
+    Path::new(launcher).file_stem().and_then(|s| s.to_str()).unwrap_or("")
+}
+
+/// The cache hits and misses counted by the compiler launcher.
+pub struct LauncherStats {
+    hits: u64,
+    misses: u64,
+}
+
+impl LauncherStats {
+    /// Reads the statistics of the configured compiler launcher, if it's
+    /// ccache or sccache.
+    pub fn read(build: &Build) -> Option<LauncherStats> {
+        let launcher = match build.config.ccache {
+            Some(ref launcher) => launcher,
+            None => return None,
+        };
+        let arg = match launcher_name(launcher) {
+            "sccache" => "--show-stats",
+            "ccache" => "-s",
+            _ => return None,
+        };
+        let out = match Command::new(launcher).arg(arg).output() {
+            Ok(ref out) if out.status.success() => {
+                String::from_utf8_lossy(&out.stdout).into_owned()
+            }
+            _ => return None,
+        };
+
+        // ccache lists `cache hit (direct)`, `cache hit (preprocessed)` and
+        // `cache miss`, sccache `Cache hits` and `Cache misses`, each followed
+        // by the count.
+        let mut stats = LauncherStats { hits: 0, misses: 0 };
+        for line in out.lines() {
+            let line = line.trim();
+            let (label, count) = match line.rfind(' ') {
+                Some(i) => (line[..i].trim().to_lowercase(), &line[i + 1..]),
+                None => continue,
+            };
+            let count = match count.parse::<u64>() {
+                Ok(count) => count,
+                Err(_) => continue,
+            };
+            if label == "cache hits" || label.starts_with("cache hit (") {
+                stats.hits += count;
+            } else if label == "cache miss" || label == "cache misses" {
+                stats.misses += count;
+            }
+        }
+        Some(stats)
+    }
+
+    /// Prints how many of the compilations since `self` was read hit the
+    /// cache, if there were any.
+    pub fn report(&self, build: &Build) {
+        let now = match LauncherStats::read(build) {
+            Some(now) => now,
+            None => return,
+        };
+        let hits = now.hits.saturating_sub(self.hits);
+        let misses = now.misses.saturating_sub(self.misses);
+        if hits + misses > 0 {
+            println!("{}: {} of {} compilations were cached ({}%)",
+                     build.config.ccache.as_ref().unwrap(), hits, hits + misses,
+                     hits * 100 / (hits + misses));
+        }
+    }
+}
+
+/// Returns the directory in which the build of LLVM for `target` is cached,
+/// or `None` if LLVM builds aren't cached.
+///
+/// The directory name is derived from the target, the commit of the LLVM
+/// submodule, the compilers and flags used and the build options in `config`.
+/// Builds of a modified LLVM checkout are never cached, as the commit
+/// doesn't describe them.
+fn llvm_cache(build: &Build, target: &str, config: &[&str]) -> Option<PathBuf> {
+    if !build.config.llvm_cache {
+        return None
+    }
+    let llvm = build.src.join("src/llvm");
+    if !llvm.join(".git").exists() {
+        return None
+    }
+    let status = output(Command::new("git").current_dir(&llvm)
+                                           .arg("status").arg("--porcelain"));
+    if !status.trim().is_empty() {
+        println!("Not caching LLVM, the src/llvm checkout has local changes");
+        return None
+    }
+    let commit = output(Command::new("git").current_dir(&llvm)
+                                           .arg("rev-parse").arg("HEAD"));
+
+    let mut hasher = DefaultHasher::new();
+    commit.trim().hash(&mut hasher);
+    config.hash(&mut hasher);
+    build.build.hash(&mut hasher);
+    build.cc(target).hash(&mut hasher);
+    build.cxx(target).ok().hash(&mut hasher);
+    build.cflags(target).hash(&mut hasher);
+    if target != build.build {
+        build.cc(&build.build).hash(&mut hasher);
+    }
+
+    let dir = build.config.llvm_cache_dir.clone()
+                   .unwrap_or_else(|| build.out.join("cache/llvm"));
+    Some(dir.join(format!("{}-{:016x}", target, hasher.finish())))
+}
+
+/// Copies the installed LLVM in `out_dir` into `cache`. Of its build
+/// directory only `FileCheck` is kept, as that's where the codegen tests run it
+/// from (see `Build::llvm_filecheck`).
+fn save_llvm_cache(out_dir: &Path, cache: &Path) {
+    // Copy to a temporary directory first so that another build sharing the
+    // cache never sees a partial copy.
+    let mut tmp = cache.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    let _ = fs::remove_dir_all(&tmp);
+    t!(fs::create_dir_all(&tmp));
+    util::cp_filtered(out_dir, &tmp, &|path| {
+        if !path.starts_with("build") {
+            return true
+        }
+        ["build", "build/bin", "build/Release", "build/Release/bin"].iter().any(|dir| {
+            path == Path::new(dir) ||
+                (path.parent() == Some(Path::new(dir)) && dir.ends_with("bin") &&
+                 path.file_name().unwrap().to_string_lossy().starts_with("FileCheck"))
+        })
+    });
+    if fs::rename(&tmp, cache).is_err() {
+        // Someone else cached the same build in the meantime.
+        let _ = fs::remove_dir_all(&tmp);
+    }
 }
 
 fn check_llvm_version(build: &Build, llvm_config: &Path) {
@@ -0,0 +1,49 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Implementation of `./x.py run`.
+//!
+//! The rules for this subcommand depend on building the tool in question, so
+//! by the time these functions are called it's ready to be run with the
+//! arguments given after `--`.
+
+use std::process::{self, Command};
+
+use flags::Subcommand;
+use {Build, Compiler};
+
+/// Runs `tool`, as built by the stage0 compiler for `host`.
+pub fn tool(build: &Build, host: &str, tool: &str) {
+    let compiler = Compiler::new(0, host);
+    exec(build, build.tool_cmd(&compiler, tool));
+}
+
+/// Runs the `rustdoc` next to `compiler`.
+pub fn rustdoc(build: &Build, compiler: &Compiler) {
+    let mut cmd = Command::new(build.rustdoc(compiler));
+    build.add_rustc_lib_path(compiler, &mut cmd);
+    exec(build, cmd);
+}
+
+/// Runs `cmd` with the arguments passed to `./x.py run`, exiting with its
+/// exit code if it fails.
+fn exec(build: &Build, mut cmd: Command) {
+    if let Subcommand::Run { ref args, .. } = build.flags.cmd {
+        cmd.args(args);
+    }
+    build.verbose(&format!("running: {:?}", cmd));
+    let status = match cmd.status() {
+        Ok(status) => status,
+        Err(e) => panic!("failed to execute command: {:?}\nerror: {}", cmd, e),
+    };
+    if !status.success() {
+        process::exit(status.code().unwrap_or(1));
+    }
+}
@@ -29,20 +29,20 @@ use build_helper::output;
 
 use Build;
 
-struct Finder {
+pub struct Finder {
     cache: HashMap<OsString, Option<PathBuf>>,
     path: OsString,
 }
 
 impl Finder {
-    fn new() -> Self {
+    pub fn new() -> Self {
         Self {
             cache: HashMap::new(),
             path: env::var_os("PATH").unwrap_or_default()
         }
     }
 
-    fn maybe_have<S: AsRef<OsStr>>(&mut self, cmd: S) -> Option<PathBuf> {
+    pub fn maybe_have<S: AsRef<OsStr>>(&mut self, cmd: S) -> Option<PathBuf> {
         let cmd: OsString = cmd.as_ref().into();
         let path = self.path.clone();
         self.cache.entry(cmd.clone()).or_insert_with(|| {
@@ -0,0 +1,240 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Local statistics about rustbuild invocations.
+//!
+//! When `build.stats` is enabled in `config.toml` every invocation of
+//! rustbuild which runs steps appends a record to `build/stats.json`, with its
+//! command line, how long it took, how often the test and LLVM caches were hit
+//! and which step failed, if any. The records never leave the build directory;
+//! `./x.py stats` summarizes them.
+//!
+//! A failing command exits the process right away, so the record of the
+//! running invocation is kept up to date in `build/stats-running.json` as each
+//! step starts. If it's still around when the next invocation starts, the
+//! previous one didn't finish and is recorded as having failed in its last
+//! step.
+
+use std::collections::HashMap;
+use std::env;
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::path::Path;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use rustc_serialize::json;
+
+use Build;
+
+/// How many records are kept in `build/stats.json`.
+const MAX_RECORDS: usize = 500;
+
+/// How many of the most recent records `./x.py stats` lists.
+const RECENT: usize = 20;
+
+#[derive(RustcEncodable, RustcDecodable, Clone)]
+struct Record {
+    args: Vec<String>,
+    /// Seconds since the Unix epoch at which the invocation started.
+    started: u64,
+    duration_secs: f64,
+    steps: usize,
+    cache_hits: usize,
+    cache_misses: usize,
+    /// The step(s) which failed, if the invocation failed.
+    failed: Option<String>,
+}
+
+impl Record {
+    fn command(&self) -> String {
+        self.args.join(" ")
+    }
+}
+
+/// Records the statistics of the running invocation, if enabled.
+pub struct Recorder<'a> {
+    build: &'a Build,
+    start: Instant,
+    record: Record,
+}
+
+impl<'a> Recorder<'a> {
+    pub fn new(build: &'a Build) -> Recorder<'a> {
+        let recorder = Recorder {
+            build: build,
+            start: Instant::now(),
+            record: Record {
+                args: env::args().skip(1).collect(),
+                started: now(),
+                duration_secs: 0.0,
+                steps: 0,
+                cache_hits: 0,
+                cache_misses: 0,
+                failed: None,
+            },
+        };
+        if build.config.stats {
+            // A leftover record is that of an invocation which failed or was
+            // interrupted.
+            let running = build.out.join("stats-running.json");
+            if let Some(record) = read(&running).pop() {
+                append(build, record);
+            }
+            let _ = fs::remove_file(&running);
+        }
+        recorder
+    }
+
+    /// Notes that `step` is about to be executed.
+    pub fn step(&mut self, step: String) {
+        if !self.build.config.stats {
+            return
+        }
+        self.record.steps += 1;
+        self.update();
+        self.record.failed = Some(step);
+        write(&self.build.out.join("stats-running.json"), &[self.record.clone()]);
+    }
+
+    /// Records the end of the invocation, in which `failed` failed if given.
+    pub fn finish(mut self, failed: Option<String>) {
+        if !self.build.config.stats {
+            return
+        }
+        self.update();
+        self.record.failed = failed;
+        append(self.build, self.record);
+        let _ = fs::remove_file(self.build.out.join("stats-running.json"));
+    }
+
+    fn update(&mut self) {
+        let elapsed = self.start.elapsed();
+        self.record.duration_secs = elapsed.as_secs() as f64 +
+                                    elapsed.subsec_nanos() as f64 / 1e9;
+        self.record.cache_hits = self.build.cache_hits.get();
+        self.record.cache_misses = self.build.cache_misses.get();
+    }
+}
+
+/// Implementation of `./x.py stats`, printing the most recent invocations
+/// and how long each command line took on average.
+pub fn summarize(build: &Build) {
+    let mut records = read(&build.out.join("stats.json"));
+    records.extend(read(&build.out.join("stats-running.json")));
+    if records.is_empty() {
+        if build.config.stats {
+            println!("no builds have been recorded yet");
+        } else {
+            println!("no builds have been recorded, set `build.stats = true` in \
+                      config.toml to record them");
+        }
+        return
+    }
+
+    let now = now();
+    println!("Recent invocations, newest last:\n");
+    let skip = records.len().saturating_sub(RECENT);
+    for record in records.iter().skip(skip) {
+        let result = match record.failed {
+            Some(ref step) => format!("failed in {}", step),
+            None => "ok".to_string(),
+        };
+        let lookups = record.cache_hits + record.cache_misses;
+        let cached = if lookups > 0 {
+            format!("{}/{} cached", record.cache_hits, lookups)
+        } else {
+            String::new()
+        };
+        println!("{:>9}  {:>9}  {:>5} steps  {:<12}  {}  {}",
+                 age(now.saturating_sub(record.started)),
+                 duration(record.duration_secs),
+                 record.steps,
+                 cached,
+                 record.command(),
+                 result);
+    }
+
+    // Group successful invocations by command line, to spot those which got
+    // slower over time.
+    let mut commands = Vec::new();
+    let mut durations = HashMap::new();
+    for record in records.iter().filter(|r| r.failed.is_none()) {
+        let command = record.command();
+        if !durations.contains_key(&command) {
+            commands.push(command.clone());
+        }
+        durations.entry(command).or_insert(Vec::new()).push(record.duration_secs);
+    }
+    println!("\nSuccessful invocations by command line:\n");
+    for command in commands {
+        let durations = &durations[&command];
+        let mean = durations.iter().sum::<f64>() / durations.len() as f64;
+        println!("{:>5} runs  mean {:>9}  last {:>9}  {}",
+                 durations.len(),
+                 duration(mean),
+                 duration(durations[durations.len() - 1]),
+                 command);
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+fn duration(secs: f64) -> String {
+    let whole = secs as u64;
+    if whole >= 3600 {
+        format!("{}h{:02}m", whole / 3600, whole % 3600 / 60)
+    } else if whole >= 60 {
+        format!("{}m{:02}s", whole / 60, whole % 60)
+    } else {
+        format!("{:.1}s", secs)
+    }
+}
+
+fn age(secs: u64) -> String {
+    if secs >= 86400 {
+        format!("{}d ago", secs / 86400)
+    } else if secs >= 3600 {
+        format!("{}h ago", secs / 3600)
+    } else if secs >= 60 {
+        format!("{}m ago", secs / 60)
+    } else {
+        "just now".to_string()
+    }
+}
+
+/// Appends `record` to `build/stats.json`, dropping the oldest records
+/// beyond `MAX_RECORDS`.
+fn append(build: &Build, record: Record) {
+    let path = build.out.join("stats.json");
+    let mut records = read(&path);
+    records.push(record);
+    let excess = records.len().saturating_sub(MAX_RECORDS);
+    write(&path, &records[excess..]);
+}
+
+// Statistics are best effort, so a missing or corrupt file just starts over
+// and failing to write one doesn't fail the build.
+
+fn read(path: &Path) -> Vec<Record> {
+    let mut contents = String::new();
+    match File::open(path).and_then(|mut f| f.read_to_string(&mut contents)) {
+        Ok(_) => json::decode(&contents).unwrap_or(Vec::new()),
+        Err(_) => Vec::new(),
+    }
+}
+
+fn write(path: &Path, records: &[Record]) {
+    if let Ok(json) = json::encode(&records) {
+        let _ = fs::create_dir_all(path.parent().unwrap());
+        let _ = File::create(path).and_then(|mut f| f.write_all(json.as_bytes()));
+    }
+}
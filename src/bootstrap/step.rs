@@ -27,8 +27,13 @@
 //! about how to define rules themselves below.
 
 use std::collections::{BTreeMap, HashSet, HashMap};
+use std::fs::File;
+use std::io::Write;
 use std::mem;
 use std::process;
+use std::time::{Duration, Instant};
+
+use rustc_serialize::json;
 
 use check::{self, TestKind};
 use compile;
@@ -37,12 +42,19 @@ use doc;
 use flags::Subcommand;
 use install;
 use native;
+use run;
+use stats;
+use util;
 use {Compiler, Build, Mode};
 
 pub fn run(build: &Build) {
     let rules = build_rules(build);
     let steps = rules.plan();
-    rules.run(&steps);
+    if build.flags.explain {
+        rules.explain(&steps);
+    } else {
+        rules.run(&steps);
+    }
 }
 
 pub fn build_rules<'a>(build: &'a Build) -> Rules {
@@ -630,6 +642,34 @@ pub fn build_rules<'a>(build: &'a Build) -> Rules {
          .dep(|s| s.name("libstd"))
          .run(move |s| compile::maybe_clean_tools(build, s.stage, s.target, Mode::Libstd));
 
+    // ========================================================================
+    // Run targets
+    //
+    // Tools which can be built on demand and run with `./x.py run`
+    {
+        let mut tool = |name, path, step, tool| {
+            rules.run_tool(name, path)
+                 .host(true)
+                 .only_build(true)
+                 .dep(move |s| s.name(step).target(s.host).stage(0))
+                 .run(move |s| run::tool(build, s.host, tool));
+        };
+        tool("run-tidy", "src/tools/tidy", "tool-tidy", "tidy");
+        tool("run-compiletest", "src/tools/compiletest", "tool-compiletest", "compiletest");
+        tool("run-error-index", "src/tools/error_index_generator", "tool-error-index",
+             "error_index_generator");
+        tool("run-unstable-book-gen", "src/tools/unstable-book-gen", "tool-unstable-book-gen",
+             "unstable-book-gen");
+        tool("run-linkchecker", "src/tools/linkchecker", "tool-linkchecker", "linkchecker");
+        tool("run-build-manifest", "src/tools/build-manifest", "tool-build-manifest",
+             "build-manifest");
+    }
+    rules.run_tool("run-rustdoc", "src/librustdoc")
+         .host(true)
+         .only_build(true)
+         .dep(|s| s.name("libtest"))
+         .run(move |s| run::rustdoc(build, &s.compiler()));
+
     // ========================================================================
     // Documentation targets
     rules.doc("doc-book", "src/doc/book")
@@ -702,7 +742,13 @@ pub fn build_rules<'a>(build: &'a Build) -> Rules {
         rules.doc(&krate.doc_step, path)
              .dep(|s| s.name("libstd-link"))
              .default(default && build.config.docs)
-             .run(move |s| doc::std(build, s.stage, s.target));
+             .run(move |s| {
+                 if default {
+                     doc::std(build, s.stage, s.target)
+                 } else {
+                     doc::krate(build, s.stage, s.target, Mode::Libstd, &krate.name)
+                 }
+             });
     }
     for (krate, path, default) in krates("test") {
         rules.doc(&krate.doc_step, path)
@@ -719,7 +765,13 @@ pub fn build_rules<'a>(build: &'a Build) -> Rules {
              .dep(|s| s.name("doc-crate-std"))
              .host(true)
              .default(default && build.config.docs)
-             .run(move |s| doc::rustc(build, s.stage, s.target));
+             .run(move |s| {
+                 if default {
+                     doc::rustc(build, s.stage, s.target)
+                 } else {
+                     doc::krate(build, s.stage, s.target, Mode::Librustc, &krate.name)
+                 }
+             });
     }
 
     // ========================================================================
@@ -781,6 +833,13 @@ pub fn build_rules<'a>(build: &'a Build) -> Rules {
          .only_host_build(true)
          .dep(move |s| tool_rust_installer(build, s))
          .run(move |s| dist::analysis(build, &s.compiler(), s.target));
+    rules.dist("dist-rustc-dev", "rustc-dev")
+         .default(build.config.dist_rustc_dev)
+         .host(true)
+         .only_host_build(true)
+         .dep(|s| s.name("librustc-link"))
+         .dep(move |s| tool_rust_installer(build, s))
+         .run(move |s| dist::rustc_dev(build, &s.compiler(), s.target));
     rules.dist("dist-rls", "rls")
          .host(true)
          .only_host_build(true)
@@ -823,7 +882,7 @@ pub fn build_rules<'a>(build: &'a Build) -> Rules {
          .default(true)
          .only_host_build(true)
          .dep(|s| s.name("dist-std"))
-         .run(move |s| install::Installer::new(build).install_std(s.stage));
+         .run(move |s| install::Installer::new(build).install_std(s.stage, s.target));
     rules.install("install-cargo", "cargo")
          .default(build.config.extended)
          .host(true)
@@ -969,6 +1028,7 @@ enum Kind {
     Dist,
     Doc,
     Install,
+    Run,
 }
 
 impl<'a> Rule<'a> {
@@ -1106,6 +1166,12 @@ impl<'a> Rules<'a> {
         self.rule(name, path, Kind::Install)
     }
 
+    /// Same as `build`, but for `Kind::Run`.
+    fn run_tool<'b>(&'b mut self, name: &'a str, path: &'a str)
+                    -> RuleBuilder<'a, 'b> {
+        self.rule(name, path, Kind::Run)
+    }
+
     fn rule<'b>(&'b mut self,
                 name: &'a str,
                 path: &'a str,
@@ -1147,6 +1213,7 @@ invalid rule dependency graph detected, was a rule added and maybe typo'd?
             "bench" => Kind::Bench,
             "dist" => Kind::Dist,
             "install" => Kind::Install,
+            "run" => Kind::Run,
             _ => return None,
         };
         let rules = self.rules.values().filter(|r| r.kind == kind);
@@ -1193,20 +1260,37 @@ invalid rule dependency graph detected, was a rule added and maybe typo'd?
         // flag on the command line.
         let (kind, paths) = match self.build.flags.cmd {
             Subcommand::Build { ref paths } => (Kind::Build, &paths[..]),
-            Subcommand::Doc { ref paths } => (Kind::Doc, &paths[..]),
+            Subcommand::Doc { ref paths, .. } => (Kind::Doc, &paths[..]),
             Subcommand::Test { ref paths, .. } => (Kind::Test, &paths[..]),
             Subcommand::Bench { ref paths, .. } => (Kind::Bench, &paths[..]),
             Subcommand::Dist { ref paths } => (Kind::Dist, &paths[..]),
-            Subcommand::Install { ref paths } => (Kind::Install, &paths[..]),
-            Subcommand::Clean => panic!(),
+            Subcommand::Install { ref paths, .. } => (Kind::Install, &paths[..]),
+            Subcommand::Run { ref paths, .. } => (Kind::Run, &paths[..]),
+            Subcommand::Clean { .. } |
+            Subcommand::Stats |
+            Subcommand::Doctor => panic!(),
         };
 
+        // Installed components, if selected, are given as the name of their
+        // install rule and optionally the one target to install them for.
+        let components = match self.build.flags.cmd {
+            Subcommand::Install { ref components, .. } => {
+                components.iter().filter_map(|c| install::parse_component(c))
+                          .collect::<Vec<_>>()
+            }
+            _ => Vec::new(),
+        };
+        let components = &components;
+
         let mut rules: Vec<_> = self.rules.values().filter_map(|rule| {
             if rule.kind != kind {
                 return None;
             }
 
-            if paths.len() == 0 && rule.default {
+            if components.len() > 0 {
+                components.iter().position(|&(name, _)| name == rule.name)
+                          .map(|priority| (rule, priority))
+            } else if paths.len() == 0 && rule.default {
                 Some((rule, 0))
             } else {
                 paths.iter().position(|path| path.ends_with(rule.path))
@@ -1244,7 +1328,11 @@ invalid rule dependency graph detected, was a rule added and maybe typo'd?
             };
 
             hosts.iter().flat_map(move |host| {
-                arr.iter().map(move |target| {
+                arr.iter().filter(move |target| {
+                    components.is_empty() || components.iter().any(|&(name, triple)| {
+                        name == rule.name && triple.map_or(true, |t| t == &target[..])
+                    })
+                }).map(move |target| {
                     self.sbuild.name(rule.name).target(target).host(host)
                 })
             })
@@ -1272,30 +1360,103 @@ invalid rule dependency graph detected, was a rule added and maybe typo'd?
         }
 
         // And finally, iterate over everything and execute it.
+        let mut timings = Vec::new();
+        let mut failed_steps = Vec::new();
+        let mut flaky_steps = Vec::new();
+        let mut stats = stats::Recorder::new(self.build);
         for step in order.iter() {
             if self.build.flags.keep_stage.map_or(false, |s| step.stage <= s) {
                 self.build.verbose(&format!("keeping step {:?}", step));
                 continue;
             }
             self.build.verbose(&format!("executing step {:?}", step));
+            stats.step(describe(step));
+            let start = Instant::now();
+            let cpu_start = util::children_cpu_time();
+            let failures = self.build.delayed_failures.get();
+            let flaky_runs = self.build.flaky_runs.get();
             (self.rules[step.name].run)(step);
+            if self.build.delayed_failures.get() > failures {
+                failed_steps.push(describe(step));
+            }
+            if self.build.flaky_runs.get() > flaky_runs {
+                flaky_steps.push(describe(step));
+            }
+            if self.build.flags.timings {
+                let cpu = match (cpu_start, util::children_cpu_time()) {
+                    (Some(start), Some(end)) => Some(secs(end - start)),
+                    _ => None,
+                };
+                timings.push(StepTiming {
+                    step: describe(step),
+                    name: step.name.to_string(),
+                    stage: step.stage,
+                    host: step.host.to_string(),
+                    target: step.target.to_string(),
+                    wall_secs: secs(start.elapsed()),
+                    cpu_secs: cpu,
+                });
+            }
+        }
+
+        // Wait for the compiletest suites run in the background with
+        // `test --parallel-suites`.
+        let (failed_suites, flaky_suites) = check::finish_suites(self.build);
+        failed_steps.extend(failed_suites);
+        flaky_steps.extend(flaky_suites);
+
+        if self.build.flags.timings {
+            write_timings(self.build, &timings);
+        }
+
+        // Report test suites which only passed with `test --retry`.
+        if flaky_steps.len() > 0 {
+            println!("\nThe following steps failed, but passed when retried:");
+            for step in flaky_steps.iter() {
+                println!("    {}", step);
+            }
         }
 
         // Check for postponed failures from `test --no-fail-fast`.
         let failures = self.build.delayed_failures.get();
         if failures > 0 {
+            println!("\nThe following steps failed:");
+            for step in failed_steps.iter() {
+                println!("    {}", step);
+            }
             println!("\n{} command(s) did not execute successfully.\n", failures);
+            stats.finish(Some(failed_steps.join(", ")));
             process::exit(1);
         }
+        stats.finish(None);
+        if flaky_steps.len() > 0 {
+            if let Some(code) = self.build.flags.cmd.flaky_exit_code() {
+                process::exit(code);
+            }
+        }
     }
 
     /// From the top level targets `steps` generate a topological ordering of
     /// all steps needed to run those steps.
     fn expand(&self, steps: &[Step<'a>]) -> Vec<Step<'a>> {
-        // First up build a graph of steps and their dependencies. The `nodes`
-        // map is a map from step to a unique number. The `edges` map is a
-        // map from these unique numbers to a list of other numbers,
-        // representing dependencies.
+        // First up build a graph of steps and their dependencies.
+        let (nodes, mut edges) = self.graph(steps);
+
+        // Now that we've built up the actual dependency graph, draw more
+        // dependency edges to satisfy the `after` dependencies field for each
+        // rule.
+        self.satisfy_after_deps(&nodes, &mut edges);
+
+        self.order(&nodes, &edges)
+    }
+
+    /// Builds the dependency graph of the top level targets `steps`.
+    ///
+    /// The returned `nodes` map is a map from step to a unique number. The
+    /// `edges` map is a map from these unique numbers to a list of other
+    /// numbers, representing dependencies.
+    fn graph(&self, steps: &[Step<'a>])
+             -> (HashMap<Step<'a>, usize>, HashMap<usize, HashSet<usize>>) {
         let mut nodes = HashMap::new();
         nodes.insert(Step::noop(), 0);
         let mut edges = HashMap::new();
@@ -1303,24 +1464,83 @@ invalid rule dependency graph detected, was a rule added and maybe typo'd?
         for step in steps {
             self.build_graph(step.clone(), &mut nodes, &mut edges);
         }
+        (nodes, edges)
+    }
 
-        // Now that we've built up the actual dependency graph, draw more
-        // dependency edges to satisfy the `after` dependencies field for each
-        // rule.
-        self.satisfy_after_deps(&nodes, &mut edges);
-
-        // And finally, perform a topological sort to return a list of steps to
-        // execute.
+    /// Performs a topological sort of the graph described by `nodes` and
+    /// `edges` to return a list of steps to execute.
+    fn order(&self,
+             nodes: &HashMap<Step<'a>, usize>,
+             edges: &HashMap<usize, HashSet<usize>>) -> Vec<Step<'a>> {
         let mut order = Vec::new();
         let mut visited = HashSet::new();
         visited.insert(0);
         let idx_to_node = nodes.iter().map(|p| (*p.1, p.0)).collect::<HashMap<_, _>>();
         for idx in 0..nodes.len() {
-            self.topo_sort(idx, &idx_to_node, &edges, &mut visited, &mut order);
+            self.topo_sort(idx, &idx_to_node, edges, &mut visited, &mut order);
         }
         order
     }
 
+    /// Prints all steps that `run` would execute for the top-level targets
+    /// `steps`, in order, along with why each of them was selected.
+    ///
+    /// Nothing is actually executed. If `--graph` was passed the dependency
+    /// graph is additionally written out in the DOT format.
+    fn explain(&self, steps: &[Step<'a>]) {
+        let (nodes, mut edges) = self.graph(steps);
+
+        // Record which steps depend on each step before the `after` edges are
+        // added, as those only affect ordering and don't pull in any steps.
+        let idx_to_node = nodes.iter().map(|p| (*p.1, p.0)).collect::<HashMap<_, _>>();
+        let mut dependents = HashMap::new();
+        for (&idx, deps) in edges.iter() {
+            for &dep in deps.iter().filter(|&&dep| dep != 0) {
+                dependents.entry(dep).or_insert(Vec::new()).push(describe(idx_to_node[&idx]));
+            }
+        }
+
+        self.satisfy_after_deps(&nodes, &mut edges);
+        let order = self.order(&nodes, &edges);
+
+        println!("build plan ({} steps):", order.len());
+        for (i, step) in order.iter().enumerate() {
+            let mut reasons = Vec::new();
+            if steps.contains(step) {
+                reasons.push("requested on the command line".to_string());
+            }
+            if let Some(users) = dependents.get_mut(&nodes[step]) {
+                users.sort();
+                reasons.push(format!("required by {}", users.join(", ")));
+            }
+            if self.build.flags.keep_stage.map_or(false, |s| step.stage <= s) {
+                reasons.push("skipped due to --keep-stage".to_string());
+            }
+            println!("{:4}. {}", i + 1, describe(step));
+            for reason in reasons {
+                println!("        {}", reason);
+            }
+        }
+
+        if let Some(ref path) = self.build.flags.graph {
+            let mut dot = String::from("digraph rustbuild {\n");
+            for step in order.iter() {
+                let mut deps = edges[&nodes[step]].iter()
+                                                 .filter(|&&dep| dep != 0)
+                                                 .map(|dep| describe(idx_to_node[dep]))
+                                                 .collect::<Vec<_>>();
+                deps.sort();
+                dot.push_str(&format!("    \"{}\";\n", describe(step)));
+                for dep in deps {
+                    dot.push_str(&format!("    \"{}\" -> \"{}\";\n", describe(step), dep));
+                }
+            }
+            dot.push_str("}\n");
+            t!(t!(File::create(path)).write_all(dot.as_bytes()));
+            println!("step graph written to {}", path.display());
+        }
+    }
+
     /// Builds the dependency graph rooted at `step`.
     ///
     /// The `nodes` and `edges` maps are filled out according to the rule
@@ -1407,13 +1627,81 @@ invalid rule dependency graph detected, was a rule added and maybe typo'd?
     }
 }
 
+/// How long a step took to execute, as recorded for `--timings`.
+#[derive(RustcEncodable)]
+struct StepTiming {
+    step: String,
+    name: String,
+    stage: u32,
+    host: String,
+    target: String,
+    /// Wall time spent in the step.
+    wall_secs: f64,
+    /// CPU time spent by the processes the step spawned, if known.
+    cpu_secs: Option<f64>,
+}
+
+fn secs(d: Duration) -> f64 {
+    d.as_secs() as f64 + d.subsec_nanos() as f64 / 1e9
+}
+
+/// Writes the `--timings` report of all executed steps, in the order they were
+/// executed, to `build/timings.json` and `build/timings.html`.
+fn write_timings(build: &Build, timings: &[StepTiming]) {
+    let json = t!(json::encode(&timings));
+    t!(t!(File::create(build.out.join("timings.json"))).write_all(json.as_bytes()));
+
+    let total = timings.iter().map(|t| t.wall_secs).sum::<f64>();
+    let longest = timings.iter().map(|t| t.wall_secs).fold(0.0, f64::max);
+    let mut html = String::from("<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<title>rustbuild timings</title>
+<style>
+body { font-family: sans-serif; }
+td { padding: 2px 8px; white-space: nowrap; }
+td.time { text-align: right; }
+div.bar { background: #4a90d9; height: 12px; }
+</style>
+</head>
+<body>
+<h1>rustbuild timings</h1>
+");
+    html.push_str(&format!("<p>{} steps executed in {:.1}s</p>\n", timings.len(), total));
+    html.push_str("<table>\n<tr><th>#</th><th>Step</th><th>Wall time</th>\
+                   <th>CPU time</th><th></th></tr>\n");
+    for (i, timing) in timings.iter().enumerate() {
+        let cpu = match timing.cpu_secs {
+            Some(cpu) => format!("{:.1}s", cpu),
+            None => "-".to_string(),
+        };
+        let width = if longest > 0.0 { timing.wall_secs / longest * 400.0 } else { 0.0 };
+        html.push_str(&format!("<tr><td>{}</td><td>{}</td><td class=\"time\">{:.1}s</td>\
+                                <td class=\"time\">{}</td>\
+                                <td><div class=\"bar\" style=\"width: {:.0}px\"></div></td>\
+                                </tr>\n",
+                               i + 1, timing.step, timing.wall_secs, cpu, width));
+    }
+    html.push_str("</table>\n</body>\n</html>\n");
+
+    let path = build.out.join("timings.html");
+    t!(t!(File::create(&path)).write_all(html.as_bytes()));
+    println!("timings report written to {}", path.display());
+}
+
+/// Human readable description of `step` for `--explain`.
+fn describe(step: &Step) -> String {
+    format!("{} (stage{}, {} -> {})", step.name, step.stage, step.host, step.target)
+}
+
 #[cfg(test)]
 mod tests {
     use std::env;
 
     use Build;
     use config::Config;
-    use flags::Flags;
+    use flags::{Flags, Subcommand};
 
     fn build(args: &[&str],
              extra_host: &[&str],
@@ -1752,6 +2040,13 @@ mod tests {
         assert!(plan.iter().any(|s| s.name.contains("valgrind")));
     }
 
+    #[test]
+    fn test_shard() {
+        // The shard is compared numerically, like compiletest does.
+        let build = build(&["test", "--test-shard", "01/4"], &[], &[]);
+        assert_eq!(build.flags.cmd.test_shard(), Some((1, 4)));
+    }
+
     #[test]
     fn test_with_a_target() {
         let build = build(&["test", "--target=C"], &[], &["C"]);
@@ -1804,4 +2099,54 @@ mod tests {
             })
         }));
     }
+
+    #[test]
+    fn install_components() {
+        let build = build(&["install", "--component", "rustc,rust-std-B"], &[], &["B"]);
+        let rules = super::build_rules(&build);
+        let plan = rules.plan();
+        println!("rules: {:#?}", plan);
+
+        let step = super::Step {
+            name: "",
+            stage: 2,
+            host: &build.build,
+            target: &build.build,
+        };
+
+        assert_eq!(plan, vec![step.name("install-rustc"),
+                              step.target("B").name("install-std")]);
+    }
+
+    #[test]
+    fn run_tool() {
+        let args = ["run", "src/tools/tidy", "--build", "A", "--", "src", "--stage", "1"];
+        let flags = Flags::parse(&args.iter().map(|s| s.to_string()).collect::<Vec<_>>());
+        match flags.cmd {
+            Subcommand::Run { ref paths, ref args } => {
+                assert_eq!(paths.len(), 1);
+                assert_eq!(*args, ["src", "--stage", "1"]);
+            }
+            _ => panic!(),
+        }
+
+        let build = build(&["run", "src/tools/tidy"], &["B"], &[]);
+        let rules = super::build_rules(&build);
+        let plan = rules.plan();
+        println!("rules: {:#?}", plan);
+
+        let step = super::Step {
+            name: "",
+            stage: 2,
+            host: &build.build,
+            target: &build.build,
+        };
+        assert_eq!(plan, vec![step.name("run-tidy")]);
+
+        let all = rules.expand(&plan);
+        println!("all rules: {:#?}", all);
+        assert!(all.contains(&step.stage(0).name("tool-tidy")));
+        assert!(all.iter().all(|s| s.stage == 0 || s.name == "run-tidy"));
+        assert!(!all.iter().any(|s| s.host == "B" || s.target == "B"));
+    }
 }
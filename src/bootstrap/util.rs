@@ -18,7 +18,7 @@ use std::fs;
 use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 use std::process::Command;
-use std::time::{SystemTime, Instant};
+use std::time::{Duration, SystemTime, Instant};
 
 use filetime::{self, FileTime};
 
@@ -178,6 +178,34 @@ impl Drop for TimeIt {
     }
 }
 
+/// Returns the CPU time, user and system combined, used so far by all child
+/// processes which have been waited for, if the platform can report it.
+pub fn children_cpu_time() -> Option<Duration> {
+    return children_cpu_time_inner();
+
+    #[cfg(unix)]
+    fn children_cpu_time_inner() -> Option<Duration> {
+        use std::mem;
+
+        let usage = unsafe {
+            let mut usage: ::libc::rusage = mem::zeroed();
+            if ::libc::getrusage(::libc::RUSAGE_CHILDREN, &mut usage) != 0 {
+                return None
+            }
+            usage
+        };
+        let time = |t: ::libc::timeval| {
+            Duration::new(t.tv_sec as u64, t.tv_usec as u32 * 1000)
+        };
+        Some(time(usage.ru_utime) + time(usage.ru_stime))
+    }
+
+    #[cfg(not(unix))]
+    fn children_cpu_time_inner() -> Option<Duration> {
+        None
+    }
+}
+
 /// Symlinks two directories, using junctions on Windows and normal symlinks on
 /// Unix.
 pub fn symlink_dir(src: &Path, dest: &Path) -> io::Result<()> {
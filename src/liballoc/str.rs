@@ -44,6 +44,7 @@ use core::str::pattern::Pattern;
 use core::str::pattern::{Searcher, ReverseSearcher, DoubleEndedSearcher};
 use core::mem;
 use core::iter::FusedIterator;
+use core::ops::Range;
 use std_unicode::str::{UnicodeStr, Utf16Encoder};
 
 use vec_deque::VecDeque;
@@ -1318,6 +1319,51 @@ impl str {
         core_str::StrExt::rsplitn(self, n, pat)
     }
 
+    /// Splits the string slice at the first match of a pattern, returning
+    /// the parts in front of and behind it.
+    ///
+    /// Returns `None` if the pattern doesn't match. Unlike `splitn(2, pat)`,
+    /// this tells a string without a match apart from one ending in it, and
+    /// doesn't build an iterator.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #![feature(str_split_once)]
+    ///
+    /// assert_eq!("key=value".split_once('='), Some(("key", "value")));
+    /// assert_eq!("a=b=c".split_once('='), Some(("a", "b=c")));
+    /// assert_eq!("key=".split_once('='), Some(("key", "")));
+    /// assert_eq!("key".split_once('='), None);
+    /// ```
+    #[unstable(feature = "str_split_once", issue = "0")]
+    #[inline]
+    pub fn split_once<'a, P: Pattern<'a>>(&'a self, pat: P) -> Option<(&'a str, &'a str)> {
+        core_str::StrExt::split_once(self, pat)
+    }
+
+    /// Splits the string slice at the last match of a pattern, returning
+    /// the parts in front of and behind it.
+    ///
+    /// Returns `None` if the pattern doesn't match.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #![feature(str_split_once)]
+    ///
+    /// assert_eq!("a=b=c".rsplit_once('='), Some(("a=b", "c")));
+    /// assert_eq!("lib/foo.rs".rsplit_once("/"), Some(("lib", "foo.rs")));
+    /// assert_eq!("foo.rs".rsplit_once("/"), None);
+    /// ```
+    #[unstable(feature = "str_split_once", issue = "0")]
+    #[inline]
+    pub fn rsplit_once<'a, P: Pattern<'a>>(&'a self, pat: P) -> Option<(&'a str, &'a str)>
+        where P::Searcher: ReverseSearcher<'a>
+    {
+        core_str::StrExt::rsplit_once(self, pat)
+    }
+
     /// An iterator over the disjoint matches of a pattern within the given string
     /// slice.
     ///
@@ -1678,6 +1724,69 @@ impl str {
         core_str::StrExt::trim_right_matches(self, pat)
     }
 
+    /// Returns the byte range of the string slice which `trim_matches`
+    /// would return.
+    ///
+    /// This is useful when both the trimmed contents and their position in
+    /// the original string are needed, e.g. to point at them in an error
+    /// message.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #![feature(str_trim_range)]
+    ///
+    /// let s = "11foo1bar11";
+    /// let range = s.trim_range_matches('1');
+    /// assert_eq!(range, 2..9);
+    /// assert_eq!(&s[range], s.trim_matches('1'));
+    ///
+    /// assert_eq!("1111".trim_range_matches('1'), 0..0);
+    /// ```
+    #[unstable(feature = "str_trim_range", issue = "0")]
+    pub fn trim_range_matches<'a, P: Pattern<'a>>(&'a self, pat: P) -> Range<usize>
+        where P::Searcher: DoubleEndedSearcher<'a>
+    {
+        core_str::StrExt::trim_range_matches(self, pat)
+    }
+
+    /// Returns the byte range of the string slice which `trim_left_matches`
+    /// would return.
+    ///
+    /// The range always ends at the end of the string.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #![feature(str_trim_range)]
+    ///
+    /// assert_eq!("  --foo".trim_left_range_matches(|c| c == ' ' || c == '-'), 4..7);
+    /// assert_eq!("::::".trim_left_range_matches("::"), 4..4);
+    /// ```
+    #[unstable(feature = "str_trim_range", issue = "0")]
+    pub fn trim_left_range_matches<'a, P: Pattern<'a>>(&'a self, pat: P) -> Range<usize> {
+        core_str::StrExt::trim_left_range_matches(self, pat)
+    }
+
+    /// Returns the byte range of the string slice which `trim_right_matches`
+    /// would return.
+    ///
+    /// The range always starts at the beginning of the string.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #![feature(str_trim_range)]
+    ///
+    /// assert_eq!("foo;;".trim_right_range_matches(';'), 0..3);
+    /// ```
+    #[unstable(feature = "str_trim_range", issue = "0")]
+    pub fn trim_right_range_matches<'a, P: Pattern<'a>>(&'a self, pat: P) -> Range<usize>
+        where P::Searcher: ReverseSearcher<'a>
+    {
+        core_str::StrExt::trim_right_range_matches(self, pat)
+    }
+
     /// Parses this string slice into another type.
     ///
     /// Because `parse` is so general, it can cause problems with type
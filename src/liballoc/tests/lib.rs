@@ -26,6 +26,8 @@
 #![feature(splice)]
 #![feature(str_checked_slicing)]
 #![feature(str_escape)]
+#![feature(str_split_once)]
+#![feature(str_trim_range)]
 #![feature(test)]
 #![feature(unboxed_closures)]
 #![feature(unicode)]
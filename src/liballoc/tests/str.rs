@@ -506,6 +506,35 @@ fn test_trim_matches() {
     assert_eq!("123foo1bar123".trim_matches(|c: char| c.is_numeric()), "foo1bar");
 }
 
+#[test]
+fn test_split_once() {
+    assert_eq!("key=value".split_once('='), Some(("key", "value")));
+    assert_eq!("a::b::c".split_once("::"), Some(("a", "b::c")));
+    assert_eq!("a::b::c".rsplit_once("::"), Some(("a::b", "c")));
+    assert_eq!("=".split_once('='), Some(("", "")));
+    assert_eq!("é1é".split_once(char::is_numeric), Some(("é", "é")));
+    assert_eq!("key".split_once('='), None);
+    assert_eq!("key".rsplit_once('='), None);
+    assert_eq!("".split_once(""), Some(("", "")));
+    assert_eq!("ab".rsplit_once(""), Some(("ab", "")));
+}
+
+#[test]
+fn test_trim_range_matches() {
+    let chars: &[char] = &['*', ' '];
+    assert_eq!(" *** foo *** ".trim_range_matches(chars), 5..8);
+    assert_eq!(" *** foo *** ".trim_left_range_matches(chars), 5..13);
+    assert_eq!(" *** foo *** ".trim_right_range_matches(chars), 0..8);
+    assert_eq!(" ***  *** ".trim_range_matches(chars), 0..0);
+    assert_eq!(" ***  *** ".trim_left_range_matches(chars), 10..10);
+    assert_eq!(" ***  *** ".trim_right_range_matches(chars), 0..0);
+    assert_eq!("".trim_range_matches(chars), 0..0);
+
+    assert_eq!("ééfooé".trim_range_matches('é'), 4..7);
+    assert_eq!("xxfooxx".trim_left_range_matches("xx"), 2..7);
+    assert_eq!("xxfooxx".trim_right_range_matches("xx"), 0..5);
+}
+
 #[test]
 fn test_trim_left() {
     assert_eq!("".trim_left(), "");
@@ -1466,7 +1495,228 @@ mod pattern {
         Reject(1, 2),
         Reject(2, 3),
     ]);
+    make_test!(anchored_start_searcher_match, ::std::str::pattern::anchor_start("bb"), "bbcbb", [
+        Match (0, 2),
+        Reject(2, 5),
+    ]);
+    make_test!(anchored_start_searcher_no_match, ::std::str::pattern::anchor_start("bb"), "abb", [
+        Reject(0, 3),
+    ]);
+    make_test!(anchored_end_searcher_match, ::std::str::pattern::anchor_end("bb"), "bbcbb", [
+        Reject(0, 3),
+        Match (3, 5),
+    ]);
+    make_test!(anchored_end_searcher_overlapping, ::std::str::pattern::anchor_end("bb"), "bbb", [
+        Reject(0, 1),
+        Match (1, 3),
+    ]);
+    make_test!(anchored_end_searcher_empty_needle, ::std::str::pattern::anchor_end(""), "├", [
+        Reject(0, 3),
+        Match (3, 3),
+    ]);
+    make_test!(anchored_start_searcher_empty_haystack, ::std::str::pattern::anchor_start('b'), "", [
+    ]);
+
+}
+
+#[test]
+fn test_anchored_pattern() {
+    use std::str::pattern::{Pattern, anchor_start, anchor_end};
+
+    assert_eq!("abab".find(anchor_start("ab")), Some(0));
+    assert_eq!("abab".rfind(anchor_start("ab")), Some(0));
+    assert_eq!("abab".find(anchor_end("ab")), Some(2));
+    assert_eq!("cab".find(anchor_start('a')), None);
+    assert_eq!("abab".matches(anchor_end(char::is_alphabetic)).collect::<Vec<_>>(), ["b"]);
+    assert_eq!("a,b,".split(anchor_end(',')).collect::<Vec<_>>(), ["a,b", ""]);
+    assert_eq!("::a::".trim_left_matches(anchor_start("::")), "a::");
+    assert!("abc".contains(anchor_start("ab")));
+    assert!(!"abc".contains(anchor_end("ab")));
+    assert!(anchor_end("bc").is_contained_in("abc"));
+    assert!(!"abc".starts_with(anchor_end("ab")));
+    assert!("abc".ends_with(anchor_end("bc")));
+}
+
+#[test]
+fn test_whole_pattern() {
+    use std::str::pattern::whole;
+
+    let haystack = "cat concat cat-like cats";
+    let is_word = char::is_alphanumeric;
+    assert_eq!(haystack.match_indices(whole("cat", is_word)).collect::<Vec<_>>(),
+               [(0, "cat"), (11, "cat")]);
+    assert_eq!(haystack.rmatch_indices(whole("cat", is_word)).collect::<Vec<_>>(),
+               [(11, "cat"), (0, "cat")]);
+    assert_eq!("x xy x".matches(whole('x', is_word)).count(), 2);
+    assert_eq!("x xy x".rfind(whole('x', is_word)), Some(5));
+    assert_eq!("éé é".find(whole("é", is_word)), Some(5));
+    assert_eq!("a1 b 2c".split(whole(char::is_alphabetic, is_word)).collect::<Vec<_>>(),
+               ["a1 ", " 2c"]);
+    assert!("x ab".contains(whole("ab", is_word)));
+    assert!(!"xab".contains(whole("ab", is_word)));
+    assert!("ab c".starts_with(whole("ab", is_word)));
+    assert!(!"abc".starts_with(whole("ab", is_word)));
+    assert_eq!("--a-b--".trim_matches(whole('-', |c| c != '-')), "-a-b-");
+    assert_eq!("a_b a".find(whole('a', |c: char| c == '_' || c.is_alphanumeric())), Some(4));
+}
+
+#[test]
+fn test_any_of_pattern() {
+    use std::str::pattern::any_of;
+
+    let separators = any_of(&["; ", ", "]);
+    assert_eq!("a = 1; b = 2, c = 3".split(separators).collect::<Vec<_>>(),
+               ["a = 1", "b = 2", "c = 3"]);
+    assert_eq!("a <= b < c".match_indices(any_of(&["<=", "<", "="])).collect::<Vec<_>>(),
+               [(2, "<="), (7, "<")]);
+    // The first literal matching at a position wins.
+    assert_eq!("a <= b".match_indices(any_of(&["<", "<="])).collect::<Vec<_>>(),
+               [(2, "<")]);
+    // The literals can overlap differently from the back.
+    assert_eq!("aaa".match_indices(any_of(&["aa"])).collect::<Vec<_>>(), [(0, "aa")]);
+    assert_eq!("aaa".rmatch_indices(any_of(&["aa"])).collect::<Vec<_>>(), [(1, "aa")]);
+    assert_eq!("xéaé".find(any_of(&["é", "a"])), Some(1));
+    assert_eq!("xéaé".rfind(any_of(&["x", "a"])), Some(3));
+    assert_eq!("ab".matches(any_of(&[""])).count(), 3);
+    assert_eq!("ab".find(any_of(&[])), None);
+    assert!("abc".contains(any_of(&["x", "bc"])));
+    assert!("abc".starts_with(any_of(&["x", "ab"])));
+    assert!(!"abc".ends_with(any_of(&["x", "ab"])));
+    assert_eq!("\r\n\nx\n".trim_left_matches(any_of(&["\r\n", "\n"])), "x\n");
+    assert_eq!("\r\n\nx\r\n".trim_right_matches(any_of(&["\r\n", "\n"])), "\r\n\nx");
+}
+
+#[test]
+fn test_ascii_case_insensitive_pattern() {
+    use std::str::pattern::AsciiCaseInsensitive as Aci;
+
+    let headers = "Content-Type: text/plain\r\nCONTENT-LENGTH: 42\r\n";
+    assert_eq!(headers.find(Aci("content-length")), Some(26));
+    assert_eq!(headers.rfind(Aci("Content-")), Some(26));
+    assert_eq!("aAaA".match_indices(Aci("aa")).collect::<Vec<_>>(), [(0, "aA"), (2, "aA")]);
+    assert_eq!("aAa".rmatch_indices(Aci("aa")).collect::<Vec<_>>(), [(1, "Aa")]);
+    assert_eq!("Éé éÉ".find(Aci("éé")), None);
+    assert_eq!("xÉé éÉ".find(Aci("Éé")), Some(1));
+    assert_eq!("a@b`c[d{".split(Aci("@")).collect::<Vec<_>>(), ["a", "b`c[d{"]);
+    assert!(!"`".contains(Aci("@")));
+    assert!(!"[{".contains(Aci("{[")));
+    assert_eq!("ab".matches(Aci("")).count(), 3);
+    assert!("README.TXT".ends_with(Aci(".txt")));
+    assert!("Http://".starts_with(Aci("HTTP")));
+    assert!(!"HTT".starts_with(Aci("HTTP")));
+    assert_eq!("XxXabc".trim_left_matches(Aci("x")), "abc");
+}
+
+#[test]
+fn test_or_pattern() {
+    use std::str::pattern::or;
+
+    let words = "one two,three".split(or(char::is_whitespace, ","));
+    assert_eq!(words.collect::<Vec<_>>(), ["one", "two", "three"]);
+    assert_eq!("a <= b".match_indices(or("<=", '<')).collect::<Vec<_>>(), [(2, "<=")]);
+    assert_eq!("a <= b".match_indices(or('<', "<=")).collect::<Vec<_>>(), [(2, "<")]);
+    // From the back, the rightmost match wins.
+    assert_eq!("a <= b".rmatch_indices(or('<', "<=")).collect::<Vec<_>>(), [(2, "<=")]);
+    assert_eq!("ab".match_indices(or("b", "")).collect::<Vec<_>>(),
+               [(0, ""), (1, "b"), (2, "")]);
+    assert_eq!("aéx".find(or('x', "y")), Some(3));
+    assert_eq!("".find(or('x', "y")), None);
+    assert!("abc".contains(or('x', "b")));
+    assert!("abc".starts_with(or('x', "a")));
+    assert!("abc".ends_with(or('x', "c")));
+    assert_eq!("\r\n\r\nx".trim_left_matches(or("\r\n", '\n')), "x");
+}
+
+#[test]
+fn test_then_pattern() {
+    use std::str::pattern::then;
+
+    let lines = "a = 1;\nb = 2;\n".split(then(';', '\n'));
+    assert_eq!(lines.collect::<Vec<_>>(), ["a = 1", "b = 2", ""]);
+    let haystack = "x1 xy x2";
+    assert_eq!(haystack.match_indices(then('x', char::is_numeric)).collect::<Vec<_>>(),
+               [(0, "x1"), (6, "x2")]);
+    assert_eq!(haystack.rfind(then('x', char::is_alphabetic)), Some(3));
+    assert_eq!("aaa".match_indices(then("a", "a")).collect::<Vec<_>>(), [(0, "aa")]);
+    assert_eq!("aaa".rmatch_indices(then("a", "a")).collect::<Vec<_>>(), [(1, "aa")]);
+    assert_eq!("abb".match_indices(then("", "b")).collect::<Vec<_>>(), [(1, "b"), (2, "b")]);
+    assert!("abc".starts_with(then("a", "b")));
+    assert!(!"abc".starts_with(then("a", "c")));
+    assert!("abc".ends_with(then("b", "c")));
+}
+
+#[test]
+fn test_searcher_state() {
+    use std::str::pattern::{Pattern, SearchStep, Searcher, ReverseSearcher, anchor_start};
+
+    // The haystack is copied to make sure that nothing refers to the first.
+    fn check<'a, P: Pattern<'a> + Clone>(pat: P, haystack: &'a str, copy: &'a str) {
+        let mut expected = pat.clone().into_searcher(haystack);
+        for _ in 0..3 {
+            expected.next();
+        }
+        let state = expected.save_state().unwrap();
+        let mut resumed = pat.into_searcher(copy);
+        resumed.restore_state(state);
+        assert_eq!(resumed.save_state(), Some(state));
+        loop {
+            let step = expected.next();
+            assert_eq!(resumed.next(), step);
+            if step == SearchStep::Done {
+                break
+            }
+        }
+    }
+
+    let haystack = "aéb aab ab éab";
+    let copy = haystack.to_string();
+    check('a', haystack, &copy);
+    check(&['a', 'é'][..], haystack, &copy);
+    check(char::is_whitespace, haystack, &copy);
+    check("ab", haystack, &copy);
+    check("", haystack, &copy);
+    check("aaaaaaaaaaaaaaaaaab", haystack, &copy);
+
+    let mut searcher = 'b'.into_searcher(haystack);
+    searcher.next_back();
+    let mut resumed = 'b'.into_searcher(&copy);
+    resumed.restore_state(searcher.save_state().unwrap());
+    assert_eq!(resumed.next_match_back(), Some((10, 11)));
 
+    assert_eq!(anchor_start('a').into_searcher(haystack).save_state(), None);
+}
+
+#[test]
+#[should_panic]
+fn test_searcher_state_different_searcher() {
+    use std::str::pattern::{Pattern, Searcher};
+    let state = 'a'.into_searcher("abc").save_state().unwrap();
+    "a".into_searcher("abc").restore_state(state);
+}
+
+#[test]
+#[should_panic(expected = "checkpoint doesn't fit the haystack")]
+fn test_searcher_state_different_haystack() {
+    use std::str::pattern::{Pattern, Searcher};
+    let mut searcher = "ab".into_searcher("xxab");
+    assert_eq!(searcher.next_match(), Some((2, 4)));
+    let state = searcher.save_state().unwrap();
+    // The search stopped at 4, which is inside of the 'é' here.
+    "ab".into_searcher("xxxé").restore_state(state);
+}
+
+#[test]
+fn test_searcher_state_different_contents() {
+    use std::str::pattern::{Pattern, SearchStep, Searcher};
+    // Only the offsets carry over to the other haystack, not what the
+    // searcher has learned about the contents of the first one.
+    let mut searcher = "bab".into_searcher("aabaaaaa");
+    assert_eq!(searcher.next(), SearchStep::Reject(0, 2));
+    let state = searcher.save_state().unwrap();
+    let mut resumed = "bab".into_searcher("aaaabbab");
+    resumed.restore_state(state);
+    assert_eq!(resumed.next_match(), Some((5, 8)));
+    assert_eq!(resumed.next_match(), None);
 }
 
 macro_rules! generate_iterator_test {
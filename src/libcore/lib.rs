@@ -94,6 +94,7 @@
 #![feature(unwind_attributes)]
 
 #![cfg_attr(stage0, feature(associated_consts))]
+#![cfg_attr(not(stage0), feature(compile_error))]
 
 #[prelude_import]
 #[allow(unused)]
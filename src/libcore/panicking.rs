@@ -29,6 +29,13 @@
 //! necessary lang items for the compiler. All panics are funneled through this
 //! one function. Currently, the actual symbol is declared in the standard
 //! library, but the location of this may change over time.
+//!
+//! If libcore is built with `--cfg truncate_panic_messages`, panic messages are
+//! first formatted into a small buffer on the stack and truncated to its size
+//! before being passed on, so that no unbounded formatting happens on panics.
+//! Its size in bytes has to be selected as well, with e.g.
+//! `--cfg 'panic_message_len="64"'`. Only 32, 64, 128, 256, 512 and 1024 are
+//! accepted, libcore fails to build with any other size.
 
 #![allow(dead_code, missing_docs)]
 #![unstable(feature = "core_panic",
@@ -36,7 +43,9 @@
                       and related macros",
             issue = "0")]
 
+use cmp;
 use fmt;
+use str;
 
 #[cold] #[inline(never)] // this is the slow path, always
 #[cfg_attr(not(stage0), lang = "panic")]
@@ -80,14 +89,79 @@ fn panic_bounds_check_old(file_line: &(&'static str, u32),
                            len, index), &(file, line, 0))
 }
 
+#[allow(improper_ctypes)]
+extern {
+    #[lang = "panic_fmt"]
+    #[unwind]
+    fn panic_impl(fmt: fmt::Arguments, file: &'static str, line: u32, col: u32) -> !;
+}
+
 #[cold] #[inline(never)]
 pub fn panic_fmt(fmt: fmt::Arguments, file_line_col: &(&'static str, u32, u32)) -> ! {
-    #[allow(improper_ctypes)]
-    extern {
-        #[lang = "panic_fmt"]
-        #[unwind]
-        fn panic_impl(fmt: fmt::Arguments, file: &'static str, line: u32, col: u32) -> !;
-    }
     let (file, line, col) = *file_line_col;
+    if cfg!(truncate_panic_messages) {
+        panic_truncated(fmt, file, line, col)
+    }
     unsafe { panic_impl(fmt, file, line, col) }
 }
+
+/// Maximum length in bytes of panic messages when libcore is built with
+/// `--cfg truncate_panic_messages`, selected with `--cfg panic_message_len`.
+#[cfg(panic_message_len = "32")]
+const TRUNCATED_MESSAGE_LEN: usize = 32;
+#[cfg(panic_message_len = "64")]
+const TRUNCATED_MESSAGE_LEN: usize = 64;
+#[cfg(panic_message_len = "128")]
+const TRUNCATED_MESSAGE_LEN: usize = 128;
+#[cfg(panic_message_len = "256")]
+const TRUNCATED_MESSAGE_LEN: usize = 256;
+#[cfg(panic_message_len = "512")]
+const TRUNCATED_MESSAGE_LEN: usize = 512;
+#[cfg(panic_message_len = "1024")]
+const TRUNCATED_MESSAGE_LEN: usize = 1024;
+
+// A `cfg` can't tell a missing value from one that isn't listed above, so
+// neither is accepted when the length is needed. Otherwise the buffer isn't
+// used, and any size will do.
+#[cfg(all(truncate_panic_messages,
+          not(any(panic_message_len = "32", panic_message_len = "64",
+                  panic_message_len = "128", panic_message_len = "256",
+                  panic_message_len = "512", panic_message_len = "1024"))))]
+compile_error!("`--cfg truncate_panic_messages` needs `--cfg panic_message_len` \
+                to be one of \"32\", \"64\", \"128\", \"256\", \"512\" or \"1024\"");
+#[cfg(not(any(panic_message_len = "32", panic_message_len = "64",
+              panic_message_len = "128", panic_message_len = "256",
+              panic_message_len = "512", panic_message_len = "1024")))]
+const TRUNCATED_MESSAGE_LEN: usize = 128;
+
+/// Formats the panic message into a fixed-size buffer on the stack, stopping
+/// as soon as the buffer is full, and panics with the truncated message.
+///
+/// Targets with tiny stacks can build libcore with
+/// `--cfg truncate_panic_messages` to bound the formatting work done before
+/// the `panic_fmt` implementation is called.
+#[cold] #[inline(never)]
+fn panic_truncated(fmt: fmt::Arguments, file: &'static str, line: u32, col: u32) -> ! {
+    struct Buffer {
+        bytes: [u8; TRUNCATED_MESSAGE_LEN],
+        len: usize,
+    }
+
+    impl fmt::Write for Buffer {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            let mut n = cmp::min(self.bytes.len() - self.len, s.len());
+            while !s.is_char_boundary(n) {
+                n -= 1;
+            }
+            self.bytes[self.len..self.len + n].copy_from_slice(&s.as_bytes()[..n]);
+            self.len += n;
+            // Stop formatting the rest of the message once the buffer is full
+            if n < s.len() { Err(fmt::Error) } else { Ok(()) }
+        }
+    }
+
+    let mut buffer = Buffer { bytes: [0; TRUNCATED_MESSAGE_LEN], len: 0 };
+    let _ = fmt::write(&mut buffer, fmt);
+    let pieces = [unsafe { str::from_utf8_unchecked(&buffer.bytes[..buffer.len]) }];
+    unsafe { panic_impl(fmt::Arguments::new_v1(&pieces, &[]), file, line, col) }
+}
@@ -23,6 +23,7 @@ use fmt;
 use iter::{Map, Cloned, FusedIterator};
 use slice::{self, SliceIndex};
 use mem;
+use ops::Range;
 
 pub mod pattern;
 
@@ -2035,6 +2036,11 @@ pub trait StrExt {
     #[stable(feature = "core", since = "1.6.0")]
     fn rsplitn<'a, P: Pattern<'a>>(&'a self, count: usize, pat: P) -> RSplitN<'a, P>
         where P::Searcher: ReverseSearcher<'a>;
+    #[unstable(feature = "str_split_once", issue = "0")]
+    fn split_once<'a, P: Pattern<'a>>(&'a self, pat: P) -> Option<(&'a str, &'a str)>;
+    #[unstable(feature = "str_split_once", issue = "0")]
+    fn rsplit_once<'a, P: Pattern<'a>>(&'a self, pat: P) -> Option<(&'a str, &'a str)>
+        where P::Searcher: ReverseSearcher<'a>;
     #[stable(feature = "core", since = "1.6.0")]
     fn split_terminator<'a, P: Pattern<'a>>(&'a self, pat: P) -> SplitTerminator<'a, P>;
     #[stable(feature = "core", since = "1.6.0")]
@@ -2081,6 +2087,14 @@ pub trait StrExt {
     #[stable(feature = "core", since = "1.6.0")]
     fn trim_right_matches<'a, P: Pattern<'a>>(&'a self, pat: P) -> &'a str
         where P::Searcher: ReverseSearcher<'a>;
+    #[unstable(feature = "str_trim_range", issue = "0")]
+    fn trim_range_matches<'a, P: Pattern<'a>>(&'a self, pat: P) -> Range<usize>
+        where P::Searcher: DoubleEndedSearcher<'a>;
+    #[unstable(feature = "str_trim_range", issue = "0")]
+    fn trim_left_range_matches<'a, P: Pattern<'a>>(&'a self, pat: P) -> Range<usize>;
+    #[unstable(feature = "str_trim_range", issue = "0")]
+    fn trim_right_range_matches<'a, P: Pattern<'a>>(&'a self, pat: P) -> Range<usize>
+        where P::Searcher: ReverseSearcher<'a>;
     #[stable(feature = "is_char_boundary", since = "1.9.0")]
     fn is_char_boundary(&self, index: usize) -> bool;
     #[stable(feature = "core", since = "1.6.0")]
@@ -2206,6 +2220,30 @@ impl StrExt for str {
         RSplitN(self.splitn(count, pat).0)
     }
 
+    #[inline]
+    fn split_once<'a, P: Pattern<'a>>(&'a self, pat: P) -> Option<(&'a str, &'a str)> {
+        match pat.into_searcher(self).next_match() {
+            Some((a, b)) => unsafe {
+                // Searcher is known to return valid indices
+                Some((self.slice_unchecked(0, a), self.slice_unchecked(b, self.len())))
+            },
+            None => None,
+        }
+    }
+
+    #[inline]
+    fn rsplit_once<'a, P: Pattern<'a>>(&'a self, pat: P) -> Option<(&'a str, &'a str)>
+        where P::Searcher: ReverseSearcher<'a>
+    {
+        match pat.into_searcher(self).next_match_back() {
+            Some((a, b)) => unsafe {
+                // Searcher is known to return valid indices
+                Some((self.slice_unchecked(0, a), self.slice_unchecked(b, self.len())))
+            },
+            None => None,
+        }
+    }
+
     #[inline]
     fn split_terminator<'a, P: Pattern<'a>>(&'a self, pat: P) -> SplitTerminator<'a, P> {
         SplitTerminator(SplitInternal {
@@ -2300,6 +2338,37 @@ impl StrExt for str {
     #[inline]
     fn trim_matches<'a, P: Pattern<'a>>(&'a self, pat: P) -> &'a str
         where P::Searcher: DoubleEndedSearcher<'a>
+    {
+        let range = self.trim_range_matches(pat);
+        unsafe {
+            // Searcher is known to return valid indices
+            self.slice_unchecked(range.start, range.end)
+        }
+    }
+
+    #[inline]
+    fn trim_left_matches<'a, P: Pattern<'a>>(&'a self, pat: P) -> &'a str {
+        let range = self.trim_left_range_matches(pat);
+        unsafe {
+            // Searcher is known to return valid indices
+            self.slice_unchecked(range.start, range.end)
+        }
+    }
+
+    #[inline]
+    fn trim_right_matches<'a, P: Pattern<'a>>(&'a self, pat: P) -> &'a str
+        where P::Searcher: ReverseSearcher<'a>
+    {
+        let range = self.trim_right_range_matches(pat);
+        unsafe {
+            // Searcher is known to return valid indices
+            self.slice_unchecked(range.start, range.end)
+        }
+    }
+
+    #[inline]
+    fn trim_range_matches<'a, P: Pattern<'a>>(&'a self, pat: P) -> Range<usize>
+        where P::Searcher: DoubleEndedSearcher<'a>
     {
         let mut i = 0;
         let mut j = 0;
@@ -2312,27 +2381,21 @@ impl StrExt for str {
         if let Some((_, b)) = matcher.next_reject_back() {
             j = b;
         }
-        unsafe {
-            // Searcher is known to return valid indices
-            self.slice_unchecked(i, j)
-        }
+        i..j
     }
 
     #[inline]
-    fn trim_left_matches<'a, P: Pattern<'a>>(&'a self, pat: P) -> &'a str {
+    fn trim_left_range_matches<'a, P: Pattern<'a>>(&'a self, pat: P) -> Range<usize> {
         let mut i = self.len();
         let mut matcher = pat.into_searcher(self);
         if let Some((a, _)) = matcher.next_reject() {
             i = a;
         }
-        unsafe {
-            // Searcher is known to return valid indices
-            self.slice_unchecked(i, self.len())
-        }
+        i..self.len()
     }
 
     #[inline]
-    fn trim_right_matches<'a, P: Pattern<'a>>(&'a self, pat: P) -> &'a str
+    fn trim_right_range_matches<'a, P: Pattern<'a>>(&'a self, pat: P) -> Range<usize>
         where P::Searcher: ReverseSearcher<'a>
     {
         let mut j = 0;
@@ -2340,10 +2403,7 @@ impl StrExt for str {
         if let Some((_, b)) = matcher.next_reject_back() {
             j = b;
         }
-        unsafe {
-            // Searcher is known to return valid indices
-            self.slice_unchecked(0, j)
-        }
+        0..j
     }
 
     #[inline]
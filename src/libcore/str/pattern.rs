@@ -88,6 +88,26 @@ pub enum SearchStep {
     Done
 }
 
+/// A checkpoint of the progress of a `Searcher`, see
+/// `Searcher::save_state()`.
+///
+/// It only consists of offsets into the haystack, and doesn't borrow it.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct SearcherState {
+    kind: StateKind,
+    front: usize,
+    back: usize,
+    /// The match flags of the empty needle searcher.
+    extra: (usize, usize),
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum StateKind {
+    CharEq,
+    EmptyNeedle,
+    TwoWay,
+}
+
 /// A searcher for a string pattern.
 ///
 /// This trait provides methods for searching for non-overlapping
@@ -149,6 +169,29 @@ pub unsafe trait Searcher<'a> {
             }
         }
     }
+
+    /// Returns a checkpoint of the progress of the search from both ends,
+    /// or `None` if this searcher doesn't support checkpoints.
+    ///
+    /// As the checkpoint only holds offsets, it can be restored into a
+    /// searcher for the same pattern over a different haystack with the same
+    /// contents, for example after the memory holding a huge haystack has
+    /// been remapped. The search then resumes where it was stopped.
+    #[inline]
+    fn save_state(&self) -> Option<SearcherState> {
+        None
+    }
+
+    /// Resumes the search from a checkpoint returned by `save_state()`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the checkpoint was taken from a different kind of searcher,
+    /// or doesn't fit the haystack of this one.
+    #[inline]
+    fn restore_state(&mut self, _state: SearcherState) {
+        panic!("the searcher doesn't support checkpoints");
+    }
 }
 
 /// A reverse searcher for a string pattern.
@@ -320,6 +363,25 @@ unsafe impl<'a, C: CharEq> Searcher<'a> for CharEqSearcher<'a, C> {
         }
         SearchStep::Done
     }
+
+    #[inline]
+    fn save_state(&self) -> Option<SearcherState> {
+        let front = self.char_indices.front_offset;
+        Some(SearcherState {
+            kind: StateKind::CharEq,
+            front: front,
+            back: front + self.char_indices.iter.iter.len(),
+            extra: (0, 0),
+        })
+    }
+
+    fn restore_state(&mut self, state: SearcherState) {
+        assert!(state.kind == StateKind::CharEq, "checkpoint of a different searcher");
+        self.char_indices = super::CharIndices {
+            front_offset: state.front,
+            iter: self.haystack[state.front..state.back].chars(),
+        };
+    }
 }
 
 unsafe impl<'a, C: CharEq> ReverseSearcher<'a> for CharEqSearcher<'a, C> {
@@ -392,6 +454,14 @@ macro_rules! searcher_methods {
         fn next_reject(&mut self) -> Option<(usize, usize)> {
             self.0.next_reject()
         }
+        #[inline]
+        fn save_state(&self) -> Option<SearcherState> {
+            self.0.save_state()
+        }
+        #[inline]
+        fn restore_state(&mut self, state: SearcherState) {
+            self.0.restore_state(state)
+        }
     };
     (reverse) => {
         #[inline]
@@ -565,6 +635,1057 @@ impl<'a, 'b> Pattern<'a> for &'b str {
 }
 
 
+/////////////////////////////////////////////////////////////////////////////
+// Anchored patterns
+/////////////////////////////////////////////////////////////////////////////
+
+/// Which end of the haystack an `Anchored` pattern has to match at.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Anchor {
+    /// The match has to start at the front of the haystack.
+    Start,
+    /// The match has to end at the back of the haystack.
+    End,
+}
+
+/// A pattern which only matches at the front or back of the haystack.
+///
+/// Created by `anchor_start` and `anchor_end`. There is at most one match,
+/// which is the same whichever direction the haystack is searched in, so the
+/// searcher is double ended even if the wrapped pattern's searcher isn't.
+#[derive(Copy, Clone, Debug)]
+pub struct Anchored<P> {
+    pat: P,
+    anchor: Anchor,
+}
+
+/// Wraps `pat` so that it only matches at the front of the haystack.
+///
+/// # Examples
+///
+/// ```
+/// #![feature(pattern)]
+///
+/// use std::str::pattern::anchor_start;
+///
+/// assert_eq!("abcab".find(anchor_start("ab")), Some(0));
+/// assert_eq!("cabab".find(anchor_start("ab")), None);
+/// assert_eq!("abab".matches(anchor_start("ab")).count(), 1);
+/// ```
+#[inline]
+pub fn anchor_start<P>(pat: P) -> Anchored<P> {
+    Anchored { pat: pat, anchor: Anchor::Start }
+}
+
+/// Wraps `pat` so that it only matches at the back of the haystack.
+///
+/// # Examples
+///
+/// ```
+/// #![feature(pattern)]
+///
+/// use std::str::pattern::anchor_end;
+///
+/// assert_eq!("abcab".find(anchor_end("ab")), Some(3));
+/// assert_eq!("ababc".find(anchor_end("ab")), None);
+/// assert_eq!("x = 1;".trim_right_matches(anchor_end(';')), "x = 1");
+/// ```
+#[inline]
+pub fn anchor_end<P>(pat: P) -> Anchored<P> {
+    Anchored { pat: pat, anchor: Anchor::End }
+}
+
+impl<P> Anchored<P> {
+    /// Returns the end of the haystack the pattern is anchored at.
+    #[inline]
+    pub fn anchor(&self) -> Anchor {
+        self.anchor
+    }
+
+    /// Returns the wrapped pattern.
+    #[inline]
+    pub fn into_inner(self) -> P {
+        self.pat
+    }
+}
+
+impl<'a, P: Pattern<'a>> Pattern<'a> for Anchored<P> where P::Searcher: ReverseSearcher<'a> {
+    type Searcher = AnchoredSearcher<P::Searcher>;
+
+    #[inline]
+    fn into_searcher(self, haystack: &'a str) -> Self::Searcher {
+        AnchoredSearcher {
+            searcher: self.pat.into_searcher(haystack),
+            anchor: self.anchor,
+            steps: [SearchStep::Done; 3],
+            front: 0,
+            back: 0,
+            searched: false,
+        }
+    }
+
+    #[inline]
+    fn is_contained_in(self, haystack: &'a str) -> bool {
+        match self.anchor {
+            Anchor::Start => self.pat.is_prefix_of(haystack),
+            Anchor::End => self.pat.is_suffix_of(haystack),
+        }
+    }
+}
+
+/// Associated type for `<Anchored<P> as Pattern<'a>>::Searcher`.
+#[derive(Clone, Debug)]
+pub struct AnchoredSearcher<S> {
+    searcher: S,
+    anchor: Anchor,
+    /// The steps covering the haystack: the rejected part in front of the
+    /// match, the match and the rejected part behind it, each if non-empty.
+    steps: [SearchStep; 3],
+    front: usize,
+    back: usize,
+    searched: bool,
+}
+
+impl<S> AnchoredSearcher<S> {
+    /// Looks for the only possible match with the wrapped searcher, then
+    /// splits the haystack into `steps`.
+    fn search<'a>(&mut self) where S: ReverseSearcher<'a> {
+        if self.searched {
+            return
+        }
+        self.searched = true;
+        let len = self.searcher.haystack().len();
+        let found = match self.anchor {
+            Anchor::Start => match self.searcher.next() {
+                SearchStep::Match(0, b) => Some((0, b)),
+                _ => None,
+            },
+            Anchor::End => match self.searcher.next_back() {
+                SearchStep::Match(a, b) if b == len => Some((a, b)),
+                _ => None,
+            },
+        };
+        let mut push = |step| {
+            self.steps[self.back] = step;
+            self.back += 1;
+        };
+        match found {
+            Some((a, b)) => {
+                if a > 0 {
+                    push(SearchStep::Reject(0, a));
+                }
+                push(SearchStep::Match(a, b));
+                if b < len {
+                    push(SearchStep::Reject(b, len));
+                }
+            }
+            None if len > 0 => push(SearchStep::Reject(0, len)),
+            None => {}
+        }
+    }
+}
+
+unsafe impl<'a, S: ReverseSearcher<'a>> Searcher<'a> for AnchoredSearcher<S> {
+    #[inline]
+    fn haystack(&self) -> &'a str {
+        self.searcher.haystack()
+    }
+
+    #[inline]
+    fn next(&mut self) -> SearchStep {
+        self.search();
+        if self.front < self.back {
+            self.front += 1;
+            self.steps[self.front - 1]
+        } else {
+            SearchStep::Done
+        }
+    }
+}
+
+unsafe impl<'a, S: ReverseSearcher<'a>> ReverseSearcher<'a> for AnchoredSearcher<S> {
+    #[inline]
+    fn next_back(&mut self) -> SearchStep {
+        self.search();
+        if self.front < self.back {
+            self.back -= 1;
+            self.steps[self.back]
+        } else {
+            SearchStep::Done
+        }
+    }
+}
+
+impl<'a, S: ReverseSearcher<'a>> DoubleEndedSearcher<'a> for AnchoredSearcher<S> {}
+
+/////////////////////////////////////////////////////////////////////////////
+// Whole word patterns
+/////////////////////////////////////////////////////////////////////////////
+
+/// A pattern which only reports the matches of the wrapped pattern which
+/// are whole words.
+///
+/// Created by `whole`. A match is a whole word if it's neither preceded nor
+/// followed by a word character, as decided by the predicate the pattern was
+/// created with, like `grep -w` does. Other matches of the wrapped pattern
+/// are rejected, which also hides any overlapping matches.
+#[derive(Copy, Clone)]
+pub struct Whole<P, F> {
+    pat: P,
+    is_word: F,
+}
+
+/// Wraps `pat` so that it only matches whole words, made up of characters
+/// for which `is_word` returns `true`, such as `char::is_alphanumeric`.
+///
+/// # Examples
+///
+/// ```
+/// #![feature(pattern)]
+///
+/// use std::str::pattern::whole;
+///
+/// let haystack = "cat concat cat-like cats";
+/// let words = haystack.match_indices(whole("cat", char::is_alphanumeric));
+/// assert_eq!(words.collect::<Vec<_>>(), [(0, "cat"), (11, "cat")]);
+///
+/// let ident = |c: char| c == '_' || c.is_alphanumeric();
+/// assert_eq!("let x_1 = x;".find(whole('x', ident)), Some(10));
+/// ```
+#[inline]
+pub fn whole<P, F: FnMut(char) -> bool>(pat: P, is_word: F) -> Whole<P, F> {
+    Whole { pat: pat, is_word: is_word }
+}
+
+impl<P, F> Whole<P, F> {
+    /// Returns the wrapped pattern.
+    #[inline]
+    pub fn into_inner(self) -> P {
+        self.pat
+    }
+}
+
+impl<P: fmt::Debug, F> fmt::Debug for Whole<P, F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Whole")
+            .field("pat", &self.pat)
+            .finish()
+    }
+}
+
+impl<'a, P: Pattern<'a>, F: FnMut(char) -> bool> Pattern<'a> for Whole<P, F> {
+    type Searcher = WholeSearcher<P::Searcher, F>;
+
+    #[inline]
+    fn into_searcher(self, haystack: &'a str) -> Self::Searcher {
+        WholeSearcher {
+            searcher: self.pat.into_searcher(haystack),
+            is_word: self.is_word,
+        }
+    }
+}
+
+/// Associated type for `<Whole<P, F> as Pattern<'a>>::Searcher`.
+#[derive(Clone)]
+pub struct WholeSearcher<S, F> {
+    searcher: S,
+    is_word: F,
+}
+
+impl<S: fmt::Debug, F> fmt::Debug for WholeSearcher<S, F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("WholeSearcher")
+            .field("searcher", &self.searcher)
+            .finish()
+    }
+}
+
+impl<S, F: FnMut(char) -> bool> WholeSearcher<S, F> {
+    /// Returns whether the match `a..b` isn't adjacent to a word character.
+    fn is_whole<'a>(&mut self, a: usize, b: usize) -> bool where S: Searcher<'a> {
+        let haystack = self.searcher.haystack();
+        let before = haystack[..a].chars().next_back();
+        let after = haystack[b..].chars().next();
+        !before.map_or(false, &mut self.is_word) && !after.map_or(false, &mut self.is_word)
+    }
+
+    /// Turns the matches which aren't whole words into rejections.
+    #[inline]
+    fn filter<'a>(&mut self, step: SearchStep) -> SearchStep where S: Searcher<'a> {
+        match step {
+            SearchStep::Match(a, b) if !self.is_whole(a, b) => SearchStep::Reject(a, b),
+            step => step,
+        }
+    }
+}
+
+unsafe impl<'a, S, F> Searcher<'a> for WholeSearcher<S, F>
+    where S: Searcher<'a>, F: FnMut(char) -> bool
+{
+    #[inline]
+    fn haystack(&self) -> &'a str {
+        self.searcher.haystack()
+    }
+
+    #[inline]
+    fn next(&mut self) -> SearchStep {
+        let step = self.searcher.next();
+        self.filter(step)
+    }
+
+    #[inline]
+    fn next_match(&mut self) -> Option<(usize, usize)> {
+        while let Some((a, b)) = self.searcher.next_match() {
+            if self.is_whole(a, b) {
+                return Some((a, b));
+            }
+        }
+        None
+    }
+
+    #[inline]
+    fn save_state(&self) -> Option<SearcherState> {
+        self.searcher.save_state()
+    }
+
+    #[inline]
+    fn restore_state(&mut self, state: SearcherState) {
+        self.searcher.restore_state(state)
+    }
+}
+
+unsafe impl<'a, S, F> ReverseSearcher<'a> for WholeSearcher<S, F>
+    where S: ReverseSearcher<'a>, F: FnMut(char) -> bool
+{
+    #[inline]
+    fn next_back(&mut self) -> SearchStep {
+        let step = self.searcher.next_back();
+        self.filter(step)
+    }
+
+    #[inline]
+    fn next_match_back(&mut self) -> Option<(usize, usize)> {
+        while let Some((a, b)) = self.searcher.next_match_back() {
+            if self.is_whole(a, b) {
+                return Some((a, b));
+            }
+        }
+        None
+    }
+}
+
+impl<'a, S, F> DoubleEndedSearcher<'a> for WholeSearcher<S, F>
+    where S: DoubleEndedSearcher<'a>, F: FnMut(char) -> bool
+{}
+
+/////////////////////////////////////////////////////////////////////////////
+// Searcher for literals
+/////////////////////////////////////////////////////////////////////////////
+
+/// The literals a `LiteralSearcher` compares the haystack against at each
+/// character boundary.
+#[doc(hidden)]
+trait Literals {
+    /// Returns the length of the first literal `haystack` starts with,
+    /// skipping the empty literals unless `empty` is set.
+    fn prefix_len(&self, haystack: &str, empty: bool) -> Option<usize>;
+
+    /// Returns the length of the first literal `haystack` ends with,
+    /// skipping the empty literals unless `empty` is set.
+    fn suffix_len(&self, haystack: &str, empty: bool) -> Option<usize>;
+}
+
+#[derive(Clone, Debug)]
+struct LiteralSearcher<'a, L: Literals> {
+    literals: L,
+    haystack: &'a str,
+    position: usize,
+    end: usize,
+    /// Whether an empty match may be reported at `position` and `end`, which
+    /// is not the case right after one was.
+    empty_fw: bool,
+    empty_bw: bool,
+}
+
+impl<'a, L: Literals> LiteralSearcher<'a, L> {
+    fn new(haystack: &'a str, literals: L) -> LiteralSearcher<'a, L> {
+        LiteralSearcher {
+            literals: literals,
+            haystack: haystack,
+            position: 0,
+            end: haystack.len(),
+            empty_fw: true,
+            empty_bw: true,
+        }
+    }
+}
+
+unsafe impl<'a, L: Literals> Searcher<'a> for LiteralSearcher<'a, L> {
+    #[inline]
+    fn haystack(&self) -> &'a str {
+        self.haystack
+    }
+
+    #[inline]
+    fn next(&mut self) -> SearchStep {
+        let a = self.position;
+        let rest = &self.haystack[a..self.end];
+        match self.literals.prefix_len(rest, self.empty_fw) {
+            Some(len) => {
+                self.position += len;
+                self.empty_fw = len > 0;
+                SearchStep::Match(a, self.position)
+            }
+            None => match rest.chars().next() {
+                Some(c) => {
+                    self.position += c.len_utf8();
+                    self.empty_fw = true;
+                    SearchStep::Reject(a, self.position)
+                }
+                None => SearchStep::Done,
+            },
+        }
+    }
+}
+
+unsafe impl<'a, L: Literals> ReverseSearcher<'a> for LiteralSearcher<'a, L> {
+    #[inline]
+    fn next_back(&mut self) -> SearchStep {
+        let b = self.end;
+        let rest = &self.haystack[self.position..b];
+        match self.literals.suffix_len(rest, self.empty_bw) {
+            Some(len) => {
+                self.end -= len;
+                self.empty_bw = len > 0;
+                SearchStep::Match(self.end, b)
+            }
+            None => match rest.chars().next_back() {
+                Some(c) => {
+                    self.end -= c.len_utf8();
+                    self.empty_bw = true;
+                    SearchStep::Reject(self.end, b)
+                }
+                None => SearchStep::Done,
+            },
+        }
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+// Alternative literals
+/////////////////////////////////////////////////////////////////////////////
+
+/// A pattern which matches any of several string literals.
+///
+/// Created by `any_of`. The haystack is searched for all of the literals at
+/// once, without allocating. Where several of them match at the same
+/// position, the first one in the list is reported, like an alternation in a
+/// regular expression, so a literal should come before its own prefixes. An
+/// empty literal matches at each character boundary, like `""` does.
+///
+/// As overlapping literals can match differently when the haystack is
+/// searched from the back, the searcher isn't double ended.
+#[derive(Copy, Clone, Debug)]
+pub struct AnyOf<'b>(&'b [&'b str]);
+
+/// Returns a pattern which matches any of the `literals`.
+///
+/// # Examples
+///
+/// ```
+/// #![feature(pattern)]
+///
+/// use std::str::pattern::any_of;
+///
+/// let fields = "a = 1; b = 2, c = 3".split(any_of(&["; ", ", "]));
+/// assert_eq!(fields.collect::<Vec<_>>(), ["a = 1", "b = 2", "c = 3"]);
+///
+/// let ops = "a <= b < c".matches(any_of(&["<=", "<", "="]));
+/// assert_eq!(ops.collect::<Vec<_>>(), ["<=", "<"]);
+/// ```
+#[inline]
+pub fn any_of<'b>(literals: &'b [&'b str]) -> AnyOf<'b> {
+    AnyOf(literals)
+}
+
+impl<'b> AnyOf<'b> {
+    /// Returns the literals the pattern matches.
+    #[inline]
+    pub fn into_inner(self) -> &'b [&'b str] {
+        self.0
+    }
+}
+
+impl<'b> Literals for AnyOf<'b> {
+    #[inline]
+    fn prefix_len(&self, haystack: &str, empty: bool) -> Option<usize> {
+        self.0.iter().find(|lit| (empty || !lit.is_empty()) && lit.is_prefix_of(haystack))
+            .map(|lit| lit.len())
+    }
+
+    #[inline]
+    fn suffix_len(&self, haystack: &str, empty: bool) -> Option<usize> {
+        self.0.iter().find(|lit| (empty || !lit.is_empty()) && lit.is_suffix_of(haystack))
+            .map(|lit| lit.len())
+    }
+}
+
+impl<'a, 'b> Pattern<'a> for AnyOf<'b> {
+    type Searcher = AnyOfSearcher<'a, 'b>;
+
+    #[inline]
+    fn into_searcher(self, haystack: &'a str) -> AnyOfSearcher<'a, 'b> {
+        AnyOfSearcher(LiteralSearcher::new(haystack, self))
+    }
+
+    #[inline]
+    fn is_prefix_of(self, haystack: &'a str) -> bool {
+        self.prefix_len(haystack, true).is_some()
+    }
+
+    #[inline]
+    fn is_suffix_of(self, haystack: &'a str) -> bool {
+        self.suffix_len(haystack, true).is_some()
+    }
+}
+
+/// Associated type for `<AnyOf<'b> as Pattern<'a>>::Searcher`.
+#[derive(Clone, Debug)]
+pub struct AnyOfSearcher<'a, 'b>(LiteralSearcher<'a, AnyOf<'b>>);
+
+unsafe impl<'a, 'b> Searcher<'a> for AnyOfSearcher<'a, 'b> {
+    searcher_methods!(forward);
+}
+
+unsafe impl<'a, 'b> ReverseSearcher<'a> for AnyOfSearcher<'a, 'b> {
+    searcher_methods!(reverse);
+}
+
+/////////////////////////////////////////////////////////////////////////////
+// ASCII case insensitive patterns
+/////////////////////////////////////////////////////////////////////////////
+
+/// A pattern which matches the wrapped string ignoring ASCII case.
+///
+/// `AsciiCaseInsensitive("content-length")` matches `"Content-Length"` as
+/// well as `"CONTENT-LENGTH"`, without making lowercase copies of the
+/// haystack. Other characters have to be equal, so `"é"` doesn't match `"É"`.
+///
+/// # Examples
+///
+/// ```
+/// #![feature(pattern)]
+///
+/// use std::str::pattern::AsciiCaseInsensitive;
+///
+/// let headers = "Content-Type: text/plain\r\nContent-Length: 42\r\n";
+/// assert_eq!(headers.find(AsciiCaseInsensitive("content-length")), Some(26));
+/// assert!("README.TXT".ends_with(AsciiCaseInsensitive(".txt")));
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct AsciiCaseInsensitive<P>(pub P);
+
+/// Returns whether `a` and `b` are equal, ignoring ASCII case.
+fn eq_ignore_ascii_case(a: &[u8], b: &[u8]) -> bool {
+    fn lower(byte: u8) -> u8 {
+        if b'A' <= byte && byte <= b'Z' { byte + (b'a' - b'A') } else { byte }
+    }
+    a.len() == b.len() && a.iter().zip(b).all(|(&a, &b)| lower(a) == lower(b))
+}
+
+impl<'b> Literals for AsciiCaseInsensitive<&'b str> {
+    #[inline]
+    fn prefix_len(&self, haystack: &str, empty: bool) -> Option<usize> {
+        let (haystack, needle) = (haystack.as_bytes(), self.0.as_bytes());
+        // Only ASCII letters differ from the needle, so a match ends at a
+        // character boundary.
+        if (empty || !needle.is_empty()) && needle.len() <= haystack.len() &&
+           eq_ignore_ascii_case(&haystack[..needle.len()], needle) {
+            Some(needle.len())
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn suffix_len(&self, haystack: &str, empty: bool) -> Option<usize> {
+        let (haystack, needle) = (haystack.as_bytes(), self.0.as_bytes());
+        if (empty || !needle.is_empty()) && needle.len() <= haystack.len() &&
+           eq_ignore_ascii_case(&haystack[haystack.len() - needle.len()..], needle) {
+            Some(needle.len())
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a, 'b> Pattern<'a> for AsciiCaseInsensitive<&'b str> {
+    type Searcher = AsciiCaseInsensitiveSearcher<'a, 'b>;
+
+    #[inline]
+    fn into_searcher(self, haystack: &'a str) -> AsciiCaseInsensitiveSearcher<'a, 'b> {
+        AsciiCaseInsensitiveSearcher(LiteralSearcher::new(haystack, self))
+    }
+
+    #[inline]
+    fn is_prefix_of(self, haystack: &'a str) -> bool {
+        self.prefix_len(haystack, true).is_some()
+    }
+
+    #[inline]
+    fn is_suffix_of(self, haystack: &'a str) -> bool {
+        self.suffix_len(haystack, true).is_some()
+    }
+}
+
+/// Associated type for `<AsciiCaseInsensitive<&'b str> as Pattern<'a>>::Searcher`.
+#[derive(Clone, Debug)]
+pub struct AsciiCaseInsensitiveSearcher<'a, 'b>(LiteralSearcher<'a, AsciiCaseInsensitive<&'b str>>);
+
+unsafe impl<'a, 'b> Searcher<'a> for AsciiCaseInsensitiveSearcher<'a, 'b> {
+    searcher_methods!(forward);
+}
+
+unsafe impl<'a, 'b> ReverseSearcher<'a> for AsciiCaseInsensitiveSearcher<'a, 'b> {
+    searcher_methods!(reverse);
+}
+
+/////////////////////////////////////////////////////////////////////////////
+// Combinators
+/////////////////////////////////////////////////////////////////////////////
+
+/// A pattern which matches either of two patterns.
+///
+/// Created by `or`. Of the matches of the two patterns, the leftmost one is
+/// reported when searching from the front, and the rightmost one when
+/// searching from the back. Where both match at the same position, the match
+/// of the first pattern wins.
+///
+/// Each pattern is searched for by its own searcher, which can't restart
+/// behind a match of the other pattern. Its matches overlapping that one are
+/// skipped, and so are any of its matches those skipped ones overlapped. The
+/// searcher isn't double ended, as the results can differ by direction.
+#[derive(Copy, Clone, Debug)]
+pub struct Or<A, B> {
+    a: A,
+    b: B,
+}
+
+/// Combines `a` and `b` into a pattern which matches either of them,
+/// preferring `a`.
+///
+/// # Examples
+///
+/// ```
+/// #![feature(pattern)]
+///
+/// use std::str::pattern::or;
+///
+/// let words = "one two,three".split(or(char::is_whitespace, ","));
+/// assert_eq!(words.collect::<Vec<_>>(), ["one", "two", "three"]);
+/// assert_eq!("a <= b".find(or("<=", '<')), Some(2));
+/// assert_eq!("a <= b".matches(or('<', "<=")).collect::<Vec<_>>(), ["<"]);
+/// ```
+#[inline]
+pub fn or<A, B>(a: A, b: B) -> Or<A, B> {
+    Or { a: a, b: b }
+}
+
+impl<A, B> Or<A, B> {
+    /// Returns the combined patterns.
+    #[inline]
+    pub fn into_inner(self) -> (A, B) {
+        (self.a, self.b)
+    }
+}
+
+impl<'a, A: Pattern<'a>, B: Pattern<'a>> Pattern<'a> for Or<A, B> {
+    type Searcher = OrSearcher<A::Searcher, B::Searcher>;
+
+    #[inline]
+    fn into_searcher(self, haystack: &'a str) -> Self::Searcher {
+        OrSearcher {
+            a: self.a.into_searcher(haystack),
+            b: self.b.into_searcher(haystack),
+            a_fw: None,
+            b_fw: None,
+            a_bw: None,
+            b_bw: None,
+            position: 0,
+            end: haystack.len(),
+            empty_fw: true,
+            empty_bw: true,
+        }
+    }
+
+    #[inline]
+    fn is_contained_in(self, haystack: &'a str) -> bool {
+        self.a.is_contained_in(haystack) || self.b.is_contained_in(haystack)
+    }
+
+    #[inline]
+    fn is_prefix_of(self, haystack: &'a str) -> bool {
+        self.a.is_prefix_of(haystack) || self.b.is_prefix_of(haystack)
+    }
+}
+
+/// Associated type for `<Or<A, B> as Pattern<'a>>::Searcher`.
+#[derive(Clone, Debug)]
+pub struct OrSearcher<A, B> {
+    a: A,
+    b: B,
+    /// The next match of each searcher from the front and from the back, if
+    /// it was looked for but not reported yet. `Some(None)` if there is none.
+    a_fw: Option<Option<(usize, usize)>>,
+    b_fw: Option<Option<(usize, usize)>>,
+    a_bw: Option<Option<(usize, usize)>>,
+    b_bw: Option<Option<(usize, usize)>>,
+    position: usize,
+    end: usize,
+    /// Whether an empty match may be reported at `position` and `end`, which
+    /// is not the case right after one was.
+    empty_fw: bool,
+    empty_bw: bool,
+}
+
+/// Returns the next match of `searcher` from the front which doesn't start
+/// before `position`, using the one in `next` if it was already found.
+fn peek_match<'a, S>(searcher: &mut S,
+                     next: &mut Option<Option<(usize, usize)>>,
+                     position: usize,
+                     empty: bool) -> Option<(usize, usize)>
+    where S: Searcher<'a>
+{
+    loop {
+        match *next {
+            Some(Some((a, b))) if a < position || (a == position && a == b && !empty) => {}
+            Some(found) => return found,
+            None => {}
+        }
+        *next = Some(searcher.next_match());
+    }
+}
+
+/// Returns the next match of `searcher` from the back which doesn't end
+/// after `end`, using the one in `next` if it was already found.
+fn peek_match_back<'a, S>(searcher: &mut S,
+                          next: &mut Option<Option<(usize, usize)>>,
+                          end: usize,
+                          empty: bool) -> Option<(usize, usize)>
+    where S: ReverseSearcher<'a>
+{
+    loop {
+        match *next {
+            Some(Some((a, b))) if b > end || (b == end && a == b && !empty) => {}
+            Some(found) => return found,
+            None => {}
+        }
+        *next = Some(searcher.next_match_back());
+    }
+}
+
+unsafe impl<'a, A: Searcher<'a>, B: Searcher<'a>> Searcher<'a> for OrSearcher<A, B> {
+    #[inline]
+    fn haystack(&self) -> &'a str {
+        self.a.haystack()
+    }
+
+    fn next(&mut self) -> SearchStep {
+        let position = self.position;
+        let a = peek_match(&mut self.a, &mut self.a_fw, position, self.empty_fw);
+        let b = peek_match(&mut self.b, &mut self.b_fw, position, self.empty_fw);
+        let (found, is_a) = match (a, b) {
+            (Some(a), Some(b)) if b.0 < a.0 => (Some(b), false),
+            (Some(a), _) => (Some(a), true),
+            (None, b) => (b, false),
+        };
+        match found {
+            // The part behind `end` was already searched from the back.
+            Some((a, b)) if b <= self.end => {
+                if a > position {
+                    self.position = a;
+                    self.empty_fw = true;
+                    return SearchStep::Reject(position, a);
+                }
+                if is_a {
+                    self.a_fw = None;
+                } else {
+                    self.b_fw = None;
+                }
+                self.position = b;
+                self.empty_fw = b > a;
+                SearchStep::Match(a, b)
+            }
+            _ if position < self.end => {
+                self.position = self.end;
+                self.empty_fw = true;
+                SearchStep::Reject(position, self.end)
+            }
+            _ => SearchStep::Done,
+        }
+    }
+}
+
+unsafe impl<'a, A, B> ReverseSearcher<'a> for OrSearcher<A, B>
+    where A: ReverseSearcher<'a>, B: ReverseSearcher<'a>
+{
+    fn next_back(&mut self) -> SearchStep {
+        let end = self.end;
+        let a = peek_match_back(&mut self.a, &mut self.a_bw, end, self.empty_bw);
+        let b = peek_match_back(&mut self.b, &mut self.b_bw, end, self.empty_bw);
+        let (found, is_a) = match (a, b) {
+            (Some(a), Some(b)) if b.1 > a.1 => (Some(b), false),
+            (Some(a), _) => (Some(a), true),
+            (None, b) => (b, false),
+        };
+        match found {
+            // The part in front of `position` was already searched from the
+            // front.
+            Some((a, b)) if a >= self.position => {
+                if b < end {
+                    self.end = b;
+                    self.empty_bw = true;
+                    return SearchStep::Reject(b, end);
+                }
+                if is_a {
+                    self.a_bw = None;
+                } else {
+                    self.b_bw = None;
+                }
+                self.end = a;
+                self.empty_bw = b > a;
+                SearchStep::Match(a, b)
+            }
+            _ if self.position < end => {
+                self.end = self.position;
+                self.empty_bw = true;
+                SearchStep::Reject(self.position, end)
+            }
+            _ => SearchStep::Done,
+        }
+    }
+}
+
+/// A pattern which matches one pattern directly followed by another.
+///
+/// Created by `then`. Searching from the front, each match of the first
+/// pattern is extended by a match of the second one starting where it ends,
+/// or dropped if there is none. Searching from the back, each match of the
+/// second pattern is extended by a match of the first one ending where it
+/// starts instead, which needs reverse searchers for both patterns.
+///
+/// Only the matches the searcher of the first pattern reports are tried, so
+/// `then("aa", "b")` doesn't find `"aab"` in `"aaab"`, where it reports the
+/// `"aa"` in front. Like with `Or`, matches overlapping the last reported one
+/// are skipped, and the searcher isn't double ended.
+#[derive(Copy, Clone, Debug)]
+pub struct Then<A, B> {
+    a: A,
+    b: B,
+}
+
+/// Combines `a` and `b` into a pattern which matches `a` directly followed
+/// by `b`.
+///
+/// # Examples
+///
+/// ```
+/// #![feature(pattern)]
+///
+/// use std::str::pattern::then;
+///
+/// let lines = "a = 1;\nb = 2;\n".split(then(';', '\n'));
+/// assert_eq!(lines.collect::<Vec<_>>(), ["a = 1", "b = 2", ""]);
+/// assert_eq!("x1 xy x2".find(then('x', char::is_numeric)), Some(0));
+/// assert_eq!("x1 xy x2".rfind(then('x', char::is_alphabetic)), Some(3));
+/// ```
+#[inline]
+pub fn then<A, B>(a: A, b: B) -> Then<A, B> {
+    Then { a: a, b: b }
+}
+
+impl<A, B> Then<A, B> {
+    /// Returns the combined patterns.
+    #[inline]
+    pub fn into_inner(self) -> (A, B) {
+        (self.a, self.b)
+    }
+}
+
+impl<'a, A, B> Pattern<'a> for Then<A, B>
+    where A: Pattern<'a> + Clone, B: Pattern<'a> + Clone
+{
+    type Searcher = ThenSearcher<'a, A, B>;
+
+    #[inline]
+    fn into_searcher(self, haystack: &'a str) -> Self::Searcher {
+        ThenSearcher {
+            a_searcher: self.a.clone().into_searcher(haystack),
+            b_searcher: self.b.clone().into_searcher(haystack),
+            a: self.a,
+            b: self.b,
+            position: 0,
+            end: haystack.len(),
+            empty_fw: true,
+            empty_bw: true,
+            next_fw: None,
+            next_bw: None,
+        }
+    }
+}
+
+/// Associated type for `<Then<A, B> as Pattern<'a>>::Searcher`.
+#[derive(Clone)]
+pub struct ThenSearcher<'a, A: Pattern<'a>, B: Pattern<'a>> {
+    a: A,
+    b: B,
+    /// Searches for the first pattern from the front, and for the second one
+    /// from the back.
+    a_searcher: A::Searcher,
+    b_searcher: B::Searcher,
+    position: usize,
+    end: usize,
+    /// Whether an empty match may be reported at `position` and `end`, which
+    /// is not the case right after one was.
+    empty_fw: bool,
+    empty_bw: bool,
+    /// The next match from the front and from the back, if it was found but
+    /// the part in front of or behind it is still to be rejected.
+    next_fw: Option<(usize, usize)>,
+    next_bw: Option<(usize, usize)>,
+}
+
+impl<'a, A: Pattern<'a>, B: Pattern<'a>> fmt::Debug for ThenSearcher<'a, A, B>
+    where A::Searcher: fmt::Debug, B::Searcher: fmt::Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ThenSearcher")
+            .field("a_searcher", &self.a_searcher)
+            .field("b_searcher", &self.b_searcher)
+            .field("position", &self.position)
+            .field("end", &self.end)
+            .finish()
+    }
+}
+
+impl<'a, A: Pattern<'a> + Clone, B: Pattern<'a> + Clone> ThenSearcher<'a, A, B> {
+    /// Finds the next match from the front.
+    fn find(&mut self) -> Option<(usize, usize)> {
+        let haystack = self.a_searcher.haystack();
+        while let Some((a, m)) = self.a_searcher.next_match() {
+            if a < self.position {
+                continue
+            }
+            if m > self.end {
+                break
+            }
+            let b = match self.b.clone().into_searcher(&haystack[m..self.end]).next() {
+                SearchStep::Match(0, len) => m + len,
+                _ => continue,
+            };
+            if a < b || a > self.position || self.empty_fw {
+                return Some((a, b));
+            }
+        }
+        None
+    }
+
+    /// Finds the next match from the back.
+    fn find_back(&mut self) -> Option<(usize, usize)>
+        where A::Searcher: ReverseSearcher<'a>, B::Searcher: ReverseSearcher<'a>
+    {
+        let haystack = self.b_searcher.haystack();
+        while let Some((m, b)) = self.b_searcher.next_match_back() {
+            if b > self.end {
+                continue
+            }
+            if m < self.position {
+                break
+            }
+            let rest = &haystack[self.position..m];
+            let a = match self.a.clone().into_searcher(rest).next_back() {
+                SearchStep::Match(a, end) if end == rest.len() => self.position + a,
+                _ => continue,
+            };
+            if a < b || b < self.end || self.empty_bw {
+                return Some((a, b));
+            }
+        }
+        None
+    }
+}
+
+unsafe impl<'a, A, B> Searcher<'a> for ThenSearcher<'a, A, B>
+    where A: Pattern<'a> + Clone, B: Pattern<'a> + Clone
+{
+    #[inline]
+    fn haystack(&self) -> &'a str {
+        self.a_searcher.haystack()
+    }
+
+    fn next(&mut self) -> SearchStep {
+        let position = self.position;
+        let found = match self.next_fw.take() {
+            Some(found) => Some(found),
+            None => self.find(),
+        };
+        match found {
+            // The part behind `end` might have been searched from the back
+            // since the match was found.
+            Some((a, b)) if b <= self.end => {
+                if a > position {
+                    self.next_fw = Some((a, b));
+                    self.position = a;
+                    self.empty_fw = true;
+                    return SearchStep::Reject(position, a);
+                }
+                self.position = b;
+                self.empty_fw = b > a;
+                SearchStep::Match(a, b)
+            }
+            _ if position < self.end => {
+                self.position = self.end;
+                self.empty_fw = true;
+                SearchStep::Reject(position, self.end)
+            }
+            _ => SearchStep::Done,
+        }
+    }
+}
+
+unsafe impl<'a, A, B> ReverseSearcher<'a> for ThenSearcher<'a, A, B>
+    where A: Pattern<'a> + Clone, B: Pattern<'a> + Clone,
+          A::Searcher: ReverseSearcher<'a>, B::Searcher: ReverseSearcher<'a>
+{
+    fn next_back(&mut self) -> SearchStep {
+        let end = self.end;
+        let found = match self.next_bw.take() {
+            Some(found) => Some(found),
+            None => self.find_back(),
+        };
+        match found {
+            Some((a, b)) if a >= self.position => {
+                if b < end {
+                    self.next_bw = Some((a, b));
+                    self.end = b;
+                    self.empty_bw = true;
+                    return SearchStep::Reject(b, end);
+                }
+                self.end = a;
+                self.empty_bw = b > a;
+                SearchStep::Match(a, b)
+            }
+            _ if self.position < end => {
+                self.end = self.position;
+                self.empty_bw = true;
+                SearchStep::Reject(self.position, end)
+            }
+            _ => SearchStep::Done,
+        }
+    }
+}
+
+
 /////////////////////////////////////////////////////////////////////////////
 // Two Way substring searcher
 /////////////////////////////////////////////////////////////////////////////
@@ -696,6 +1817,58 @@ unsafe impl<'a, 'b> Searcher<'a> for StrSearcher<'a, 'b> {
             }
         }
     }
+
+    fn save_state(&self) -> Option<SearcherState> {
+        Some(match self.searcher {
+            StrSearcherImpl::Empty(ref searcher) => SearcherState {
+                kind: StateKind::EmptyNeedle,
+                front: searcher.position,
+                back: searcher.end,
+                extra: (searcher.is_match_fw as usize, searcher.is_match_bw as usize),
+            },
+            StrSearcherImpl::TwoWay(ref searcher) => SearcherState {
+                kind: StateKind::TwoWay,
+                front: searcher.position,
+                back: searcher.end,
+                extra: (0, 0),
+            },
+        })
+    }
+
+    fn restore_state(&mut self, state: SearcherState) {
+        match self.searcher {
+            StrSearcherImpl::Empty(ref mut searcher) => {
+                assert!(state.kind == StateKind::EmptyNeedle, "checkpoint of a different searcher");
+                assert!(state.front <= state.back &&
+                        self.haystack.is_char_boundary(state.front) &&
+                        self.haystack.is_char_boundary(state.back),
+                        "checkpoint doesn't fit the haystack");
+                searcher.position = state.front;
+                searcher.end = state.back;
+                searcher.is_match_fw = state.extra.0 != 0;
+                searcher.is_match_bw = state.extra.1 != 0;
+            }
+            StrSearcherImpl::TwoWay(ref mut searcher) => {
+                assert!(state.kind == StateKind::TwoWay, "checkpoint of a different searcher");
+                // The steps are sliced out of the haystack by their users, so
+                // they must start and end at character boundaries.
+                assert!(state.front <= state.back &&
+                        self.haystack.is_char_boundary(state.front) &&
+                        self.haystack.is_char_boundary(state.back),
+                        "checkpoint doesn't fit the haystack");
+                searcher.position = state.front;
+                searcher.end = state.back;
+                // The memories record how much of the needle is known to
+                // match the haystack already, which needn't hold for this
+                // one. Forget them, except for the `usize::MAX` marking the
+                // long period case, which depends on the needle only.
+                if searcher.memory != usize::MAX {
+                    searcher.memory = 0;
+                    searcher.memory_back = self.needle.len();
+                }
+            }
+        }
+    }
 }
 
 unsafe impl<'a, 'b> ReverseSearcher<'a> for StrSearcher<'a, 'b> {
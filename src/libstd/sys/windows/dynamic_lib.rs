@@ -8,8 +8,6 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use os::windows::prelude::*;
-
 use ffi::{CString, OsStr};
 use io;
 use sys::c;
@@ -20,10 +18,7 @@ pub struct DynamicLibrary {
 
 impl DynamicLibrary {
     pub fn open(filename: &str) -> io::Result<DynamicLibrary> {
-        let filename = OsStr::new(filename)
-                             .encode_wide()
-                             .chain(Some(0))
-                             .collect::<Vec<_>>();
+        let filename = super::to_u16s_unchecked(OsStr::new(filename));
         let result = unsafe {
             c::LoadLibraryW(filename.as_ptr())
         };
@@ -15,6 +15,7 @@ use ffi::{OsStr, OsString};
 use io::{self, ErrorKind};
 use os::windows::ffi::{OsStrExt, OsStringExt};
 use path::PathBuf;
+use sys_common::AsInner;
 use time::Duration;
 
 #[macro_use] pub mod compat;
@@ -80,17 +81,26 @@ pub fn decode_error_kind(errno: i32) -> ErrorKind {
 
 pub fn to_u16s<S: AsRef<OsStr>>(s: S) -> io::Result<Vec<u16>> {
     fn inner(s: &OsStr) -> io::Result<Vec<u16>> {
-        let mut maybe_result: Vec<u16> = s.encode_wide().collect();
-        if maybe_result.iter().any(|&u| u == 0) {
+        let maybe_result = to_u16s_unchecked(s);
+        if maybe_result[..maybe_result.len() - 1].iter().any(|&u| u == 0) {
             return Err(io::Error::new(io::ErrorKind::InvalidInput,
                                       "strings passed to WinAPI cannot contain NULs"));
         }
-        maybe_result.push(0);
         Ok(maybe_result)
     }
     inner(s.as_ref())
 }
 
+/// Like `to_u16s`, but without checking for NULs in `s`, which then
+/// truncate the string seen by WinAPI.
+pub fn to_u16s_unchecked(s: &OsStr) -> Vec<u16> {
+    let s = &s.as_inner().inner;
+    let mut result = Vec::with_capacity(s.encoded_wide_len() + 1);
+    s.encode_wide_into(&mut result);
+    result.push(0);
+    result
+}
+
 // Many Windows APIs follow a pattern of where we hand a buffer and then they
 // will report back to us how large the buffer should be or how many bytes
 // currently reside in the buffer. This function is an abstraction over these
@@ -24,8 +24,9 @@ use ptr;
 use slice;
 use sys::{c, cvt};
 use sys::handle::Handle;
+use sys_common::AsInner;
 
-use super::to_u16s;
+use super::{to_u16s, to_u16s_unchecked};
 
 pub fn errno() -> i32 {
     unsafe { c::GetLastError() as i32 }
@@ -206,15 +207,13 @@ pub fn join_paths<I, T>(paths: I) -> Result<OsString, JoinPathsError>
     for (i, path) in paths.enumerate() {
         let path = path.as_ref();
         if i > 0 { joined.push(sep) }
-        let v = path.encode_wide().collect::<Vec<u16>>();
-        if v.contains(&(b'"' as u16)) {
+        let start = joined.len();
+        path.as_inner().inner.encode_wide_into(&mut joined);
+        if joined[start..].contains(&(b'"' as u16)) {
             return Err(JoinPathsError)
-        } else if v.contains(&sep) {
+        } else if joined[start..].contains(&sep) {
+            joined.insert(start, b'"' as u16);
             joined.push(b'"' as u16);
-            joined.extend_from_slice(&v[..]);
-            joined.push(b'"' as u16);
-        } else {
-            joined.extend_from_slice(&v[..]);
         }
     }
 
@@ -244,9 +243,7 @@ pub fn getcwd() -> io::Result<PathBuf> {
 }
 
 pub fn chdir(p: &path::Path) -> io::Result<()> {
-    let p: &OsStr = p.as_ref();
-    let mut p = p.encode_wide().collect::<Vec<_>>();
-    p.push(0);
+    let p = to_u16s_unchecked(p.as_ref());
 
     cvt(unsafe {
         c::SetCurrentDirectoryW(p.as_ptr())
@@ -8,8 +8,6 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use os::windows::prelude::*;
-
 use ffi::OsStr;
 use io;
 use mem;
@@ -75,10 +73,7 @@ pub fn anon_pipe(ours_readable: bool) -> io::Result<Pipes> {
             name = format!(r"\\.\pipe\__rust_anonymous_pipe1__.{}.{}",
                            c::GetCurrentProcessId(),
                            key);
-            let wide_name = OsStr::new(&name)
-                                  .encode_wide()
-                                  .chain(Some(0))
-                                  .collect::<Vec<_>>();
+            let wide_name = super::to_u16s_unchecked(OsStr::new(&name));
             let mut flags = c::FILE_FLAG_FIRST_PIPE_INSTANCE |
                 c::FILE_FLAG_OVERLAPPED;
             if ours_readable {
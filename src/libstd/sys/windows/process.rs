@@ -514,8 +514,7 @@ fn make_dirp(d: Option<&OsString>) -> io::Result<(*const u16, Vec<u16>)> {
 
     match d {
         Some(dir) => {
-            let mut dir_str: Vec<u16> = ensure_no_nuls(dir)?.encode_wide().collect();
-            dir_str.push(0);
+            let dir_str = sys::to_u16s_unchecked(ensure_no_nuls(dir)?);
             Ok((dir_str.as_ptr(), dir_str))
         },
         None => Ok((ptr::null(), Vec::new()))
@@ -30,6 +30,7 @@ use core::str::next_code_point;
 use ascii::*;
 use borrow::Cow;
 use char;
+use cmp;
 use fmt;
 use hash::{Hash, Hasher};
 use iter::FromIterator;
@@ -41,6 +42,13 @@ use sys_common::AsInner;
 
 const UTF8_REPLACEMENT_CHARACTER: &'static str = "\u{FFFD}";
 
+/// The version of the format written by `Wtf8::to_portable_bytes`.
+const PORTABLE_BYTES_VERSION: u8 = 1;
+
+/// Introduces an unpaired surrogate in the portable byte form. It never occurs
+/// in UTF-8.
+const PORTABLE_SURROGATE_ESCAPE: u8 = 0xFF;
+
 /// A Unicode code point: from U+0000 to U+10FFFF.
 ///
 /// Compare with the `char` type,
@@ -281,6 +289,16 @@ impl Wtf8Buf {
         self.push_code_point_unchecked(CodePoint::from_char(c))
     }
 
+    /// Append `n` copies of a Unicode scalar value at the end of the string.
+    pub fn extend_with_char(&mut self, c: char, n: usize) {
+        let mut bytes = [0; 4];
+        let bytes = c.encode_utf8(&mut bytes).as_bytes();
+        self.bytes.reserve(bytes.len() * n);
+        for _ in 0..n {
+            self.bytes.extend_from_slice(bytes);
+        }
+    }
+
     /// Append a code point at the end of the string.
     ///
     /// This replaces newly paired surrogates at the boundary
@@ -313,6 +331,23 @@ impl Wtf8Buf {
         self.bytes.truncate(new_len)
     }
 
+    /// Removes the last code point from the string and returns it,
+    /// or `None` if the string is empty.
+    ///
+    /// A supplementary code point is removed as a whole,
+    /// even if it was created by pairing two pushed surrogates.
+    #[inline]
+    pub fn pop(&mut self) -> Option<CodePoint> {
+        let start = match (&*self).last_code_point_start() {
+            Some(start) => start,
+            None => return None,
+        };
+        let code_point = next_code_point(&mut self.bytes[start..].iter())
+            .map(|c| CodePoint { value: c });
+        self.bytes.truncate(start);
+        code_point
+    }
+
     /// Consumes the WTF-8 string and tries to convert it to UTF-8.
     ///
     /// This does not copy the data.
@@ -346,6 +381,89 @@ impl Wtf8Buf {
         }
     }
 
+    /// Consumes the WTF-8 string and converts it to UTF-8, letting the
+    /// caller decide how each unpaired surrogate is represented.
+    ///
+    /// `f` is called with each surrogate (as a three-byte `Wtf8` slice)
+    /// and appends its replacement to the output string, so that it can be
+    /// formatted on the fly, e.g. as `\u{D800}`.
+    /// This does not copy the data if there are no surrogates.
+    pub fn into_string_with<F>(self, mut f: F) -> String
+        where F: FnMut(&Wtf8, &mut String)
+    {
+        let surrogate_pos = match self.next_surrogate(0) {
+            None => return unsafe { String::from_utf8_unchecked(self.bytes) },
+            Some((pos, _)) => pos,
+        };
+        let mut string = String::with_capacity(self.len());
+        let mut pos = 0;
+        let mut next = Some(surrogate_pos);
+        while let Some(surrogate_pos) = next {
+            string.push_str(unsafe { str::from_utf8_unchecked(&self.bytes[pos..surrogate_pos]) });
+            pos = surrogate_pos + 3;
+            f(&self[surrogate_pos..pos], &mut string);
+            next = self.next_surrogate(pos).map(|(surrogate_pos, _)| surrogate_pos);
+        }
+        string.push_str(unsafe { str::from_utf8_unchecked(&self.bytes[pos..]) });
+        string
+    }
+
+    /// Consumes the WTF-8 string and returns its underlying encoded bytes.
+    #[inline]
+    pub fn into_encoded_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    /// Creates a WTF-8 string from its encoded bytes, without checking
+    /// that they are well-formed WTF-8.
+    ///
+    /// The bytes must have been produced by `into_encoded_bytes`
+    /// (or otherwise be well-formed WTF-8 containing no surrogate pairs).
+    #[inline]
+    pub unsafe fn from_encoded_bytes_unchecked(bytes: Vec<u8>) -> Wtf8Buf {
+        Wtf8Buf { bytes: bytes }
+    }
+
+    /// Decodes a string from the byte form written by `Wtf8::to_portable_bytes`.
+    ///
+    /// Returns `None` if the bytes are not of a known version of the format,
+    /// or are malformed.
+    pub fn from_portable_bytes(bytes: &[u8]) -> Option<Wtf8Buf> {
+        let mut rest = match bytes.split_first() {
+            Some((&PORTABLE_BYTES_VERSION, rest)) => rest,
+            _ => return None,
+        };
+        let mut string = Wtf8Buf::with_capacity(rest.len());
+        loop {
+            let end = rest.iter()
+                          .position(|&b| b == PORTABLE_SURROGATE_ESCAPE)
+                          .unwrap_or(rest.len());
+            match str::from_utf8(&rest[..end]) {
+                Ok(s) => string.push_str(s),
+                Err(_) => return None,
+            }
+            if end == rest.len() {
+                return Some(string)
+            }
+            if rest.len() - end < 3 {
+                return None
+            }
+            let surrogate = (rest[end + 1] as u16) << 8 | rest[end + 2] as u16;
+            match surrogate {
+                // A surrogate pair is written as the supplementary code point
+                // instead, so that every string has exactly one encoding.
+                0xDC00...0xDFFF if end == 0 && string.final_lead_surrogate().is_some() => {
+                    return None
+                }
+                0xD800...0xDFFF => {
+                    string.push_code_point_unchecked(CodePoint { value: surrogate as u32 })
+                }
+                _ => return None,
+            }
+            rest = &rest[end + 3..];
+        }
+    }
+
     /// Converts this `Wtf8Buf` into a boxed `Wtf8`.
     #[inline]
     pub fn into_box(self) -> Box<Wtf8> {
@@ -387,6 +505,109 @@ impl Extend<CodePoint> for Wtf8Buf {
     }
 }
 
+/// Append WTF-8 slices from an iterator to the string.
+///
+/// This replaces surrogate code point pairs with supplementary code points,
+/// like concatenating ill-formed UTF-16 strings effectively would.
+impl<'a> Extend<&'a Wtf8> for Wtf8Buf {
+    fn extend<T: IntoIterator<Item=&'a Wtf8>>(&mut self, iter: T) {
+        for slice in iter {
+            self.push_wtf8(slice);
+        }
+    }
+}
+
+/// Concatenates a WTF-8 slice onto the string, as `push_wtf8` does.
+impl<'a> ops::Add<&'a Wtf8> for Wtf8Buf {
+    type Output = Wtf8Buf;
+
+    #[inline]
+    fn add(mut self, other: &Wtf8) -> Wtf8Buf {
+        self.push_wtf8(other);
+        self
+    }
+}
+
+/// Appends a WTF-8 slice to the string, as `push_wtf8` does.
+impl<'a> ops::AddAssign<&'a Wtf8> for Wtf8Buf {
+    #[inline]
+    fn add_assign(&mut self, other: &Wtf8) {
+        self.push_wtf8(other);
+    }
+}
+
+/// Create a new WTF-8 string from potentially ill-formed UTF-16 code units.
+///
+/// Surrogate pairs are decoded to supplementary code points,
+/// unpaired surrogates are kept as is.
+impl FromIterator<u16> for Wtf8Buf {
+    fn from_iter<T: IntoIterator<Item=u16>>(iter: T) -> Wtf8Buf {
+        let iterator = iter.into_iter();
+        let (low, _high) = iterator.size_hint();
+        let mut decoder = Wtf16Decoder::with_capacity(low);
+        for unit in iterator {
+            decoder.push(unit);
+        }
+        decoder.finish()
+    }
+}
+
+/// Incremental decoder of potentially ill-formed UTF-16 into WTF-8.
+///
+/// Code units can be pushed as they become available, for example from a
+/// read loop or an API callback, without buffering the whole wide string.
+/// A surrogate pair split between two pushes is still decoded to a single
+/// supplementary code point.
+#[derive(Clone, Debug)]
+pub struct Wtf16Decoder {
+    string: Wtf8Buf,
+}
+
+impl Wtf16Decoder {
+    /// Creates a new decoder.
+    #[inline]
+    pub fn new() -> Wtf16Decoder {
+        Wtf16Decoder { string: Wtf8Buf::new() }
+    }
+
+    /// Creates a new decoder with room for at least `n` code units of ASCII.
+    #[inline]
+    pub fn with_capacity(n: usize) -> Wtf16Decoder {
+        Wtf16Decoder { string: Wtf8Buf::with_capacity(n) }
+    }
+
+    /// Decodes one code unit.
+    #[inline]
+    pub fn push(&mut self, unit: u16) {
+        // `Wtf8Buf::push` joins a trail surrogate with a preceding lead
+        // surrogate, which is all the state this decoder needs.
+        self.string.push(CodePoint { value: unit as u32 })
+    }
+
+    /// Decodes a slice of code units.
+    pub fn push_slice(&mut self, units: &[u16]) {
+        self.string.reserve(units.len());
+        for &unit in units {
+            self.push(unit);
+        }
+    }
+
+    /// Returns the string decoded so far.
+    ///
+    /// If the last code unit pushed is a lead surrogate, it is included
+    /// as an unpaired surrogate.
+    #[inline]
+    pub fn as_wtf8(&self) -> &Wtf8 {
+        &self.string
+    }
+
+    /// Consumes the decoder and returns the decoded string.
+    #[inline]
+    pub fn finish(self) -> Wtf8Buf {
+        self.string
+    }
+}
+
 /// A borrowed slice of well-formed WTF-8 data.
 ///
 /// Similar to `&str`, but can additionally contain surrogate code points
@@ -405,35 +626,11 @@ impl AsInner<[u8]> for Wtf8 {
 /// Example: `"a\u{D800}"` for a slice with code points [U+0061, U+D800]
 impl fmt::Debug for Wtf8 {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        fn write_str_escaped(f: &mut fmt::Formatter, s: &str) -> fmt::Result {
-            use fmt::Write;
-            for c in s.chars().flat_map(|c| c.escape_debug()) {
-                f.write_char(c)?
-            }
-            Ok(())
-        }
-
+        use fmt::Write;
         formatter.write_str("\"")?;
-        let mut pos = 0;
-        loop {
-            match self.next_surrogate(pos) {
-                None => break,
-                Some((surrogate_pos, surrogate)) => {
-                    write_str_escaped(
-                        formatter,
-                        unsafe { str::from_utf8_unchecked(
-                            &self.bytes[pos .. surrogate_pos]
-                        )},
-                    )?;
-                    write!(formatter, "\\u{{{:x}}}", surrogate)?;
-                    pos = surrogate_pos + 3;
-                }
-            }
+        for c in self.escape_debug() {
+            formatter.write_char(c)?
         }
-        write_str_escaped(
-            formatter,
-            unsafe { str::from_utf8_unchecked(&self.bytes[pos..]) },
-        )?;
         formatter.write_str("\"")
     }
 }
@@ -480,6 +677,69 @@ impl Wtf8 {
         mem::transmute(value)
     }
 
+    /// Creates a WTF-8 slice from its encoded bytes, as returned by
+    /// `as_encoded_bytes`.
+    ///
+    /// The bytes must be well-formed WTF-8: generalized UTF-8 which may
+    /// encode surrogates, but never a lead surrogate directly followed by a
+    /// trail surrogate, as such a pair is encoded as a supplementary code
+    /// point instead. Slices of the bytes of a `Wtf8` split on code point
+    /// boundaries, and any valid UTF-8, satisfy this. Since the bytes aren't
+    /// checked, this function is marked unsafe.
+    #[inline]
+    pub unsafe fn from_encoded_bytes_unchecked(bytes: &[u8]) -> &Wtf8 {
+        Wtf8::from_bytes_unchecked(bytes)
+    }
+
+    /// Returns the encoded bytes of the string, without copying.
+    ///
+    /// The bytes are valid UTF-8 exactly if the string has no surrogates,
+    /// i.e. if `as_str` returns `Some`.
+    #[inline]
+    pub fn as_encoded_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Encodes the string in a stable, versioned byte form, for persisting it
+    /// across platforms and versions of the standard library.
+    ///
+    /// Unlike the encoded bytes, whose representation of surrogates is an
+    /// implementation detail, the format is fixed: a version byte, currently
+    /// 1, followed by the string as UTF-8, with each unpaired surrogate written
+    /// as the byte 0xFF followed by the surrogate in big endian.
+    /// `Wtf8Buf::from_portable_bytes` decodes it.
+    pub fn to_portable_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(1 + self.len());
+        bytes.push(PORTABLE_BYTES_VERSION);
+        for chunk in self.chunks() {
+            bytes.extend_from_slice(chunk.valid.as_bytes());
+            if let Some(surrogate) = chunk.surrogate {
+                bytes.push(PORTABLE_SURROGATE_ESCAPE);
+                bytes.push((surrogate >> 8) as u8);
+                bytes.push(surrogate as u8);
+            }
+        }
+        bytes
+    }
+
+    /// Creates a new string by repeating this one `n` times.
+    ///
+    /// If the string ends with a lead surrogate and starts with a trail
+    /// surrogate, they are joined into a supplementary code point
+    /// between each copy, like repeating an ill-formed UTF-16 string would.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the capacity of the result would overflow.
+    pub fn repeat(&self, n: usize) -> Wtf8Buf {
+        let capacity = self.len().checked_mul(n).expect("capacity overflow");
+        let mut string = Wtf8Buf::with_capacity(capacity);
+        for _ in 0..n {
+            string.push_wtf8(self);
+        }
+        string
+    }
+
     /// Returns the length, in WTF-8 bytes.
     #[inline]
     pub fn len(&self) -> usize {
@@ -511,6 +771,99 @@ impl Wtf8 {
         Wtf8CodePoints { bytes: self.bytes.iter() }
     }
 
+    /// Returns an iterator over the chars of the string, with unpaired
+    /// surrogates replaced by U+FFFD REPLACEMENT CHARACTER.
+    ///
+    /// These are the chars of `to_string_lossy()`, without allocating.
+    #[inline]
+    pub fn chars_lossy(&self) -> CharsLossy {
+        CharsLossy { code_points: self.code_points() }
+    }
+
+    /// Returns an iterator over the chars of the string and their byte
+    /// positions, with unpaired surrogates replaced by U+FFFD REPLACEMENT
+    /// CHARACTER.
+    ///
+    /// The positions are those in this string, where a replaced surrogate
+    /// takes three bytes like the replacement character.
+    #[inline]
+    pub fn char_indices_lossy(&self) -> CharIndicesLossy {
+        CharIndicesLossy { front_offset: 0, code_points: self.code_points() }
+    }
+
+    /// Returns an iterator over the chars of the string escaped with
+    /// `char::escape_debug`.
+    ///
+    /// Unpaired surrogates are escaped as `\u{dXXX}`.
+    #[inline]
+    pub fn escape_debug(&self) -> EscapeDebug {
+        EscapeDebug { inner: Escape::new(self, escape_debug_char) }
+    }
+
+    /// Returns an iterator over the chars of the string escaped with
+    /// `char::escape_default`.
+    ///
+    /// Unpaired surrogates are escaped as `\u{dXXX}`.
+    #[inline]
+    pub fn escape_default(&self) -> EscapeDefault {
+        EscapeDefault { inner: Escape::new(self, escape_default_char) }
+    }
+
+    /// Returns an iterator over the string split at its unpaired surrogates,
+    /// into chunks of valid UTF-8 each followed by one surrogate.
+    ///
+    /// This allows processing the string as UTF-8 without allocating,
+    /// with the caller choosing how to represent the surrogates.
+    #[inline]
+    pub fn chunks(&self) -> Wtf8Chunks {
+        Wtf8Chunks { rest: &self.bytes }
+    }
+
+    /// Returns an iterator over the lines of the string.
+    ///
+    /// Like `str::lines`, lines are ended by `\n` or `\r\n`, which are not
+    /// part of the yielded lines, and a final line ending is optional.
+    #[inline]
+    pub fn lines(&self) -> Wtf8Lines {
+        Wtf8Lines { rest: &self.bytes }
+    }
+
+    /// Returns an iterator over the non-empty substrings of the string
+    /// separated by ASCII whitespace.
+    ///
+    /// Surrogates, like any other non-ASCII code point, are never whitespace.
+    #[inline]
+    pub fn split_ascii_whitespace(&self) -> Wtf8SplitAsciiWhitespace {
+        Wtf8SplitAsciiWhitespace { rest: &self.bytes }
+    }
+
+    /// Returns an iterator over all overlapping windows
+    /// of `size` code points of the string.
+    ///
+    /// No windows are yielded if the string has fewer than `size` code points.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is 0.
+    #[inline]
+    pub fn code_point_windows(&self, size: usize) -> CodePointWindows {
+        assert!(size != 0, "window size must be non-zero");
+        let mut end = Some(0);
+        for _ in 0..size {
+            end = end.and_then(|end| self.next_code_point_boundary(end));
+        }
+        CodePointWindows { wtf8: self, start: 0, end: end }
+    }
+
+    /// Returns the last code point of the string,
+    /// or `None` if the string is empty.
+    #[inline]
+    pub fn last_code_point(&self) -> Option<CodePoint> {
+        self.last_code_point_start().and_then(|start| {
+            next_code_point(&mut self.bytes[start..].iter())
+        }).map(|c| CodePoint { value: c })
+    }
+
     /// Tries to convert the string to UTF-8 and return a `&str` slice.
     ///
     /// Returns `None` if the string contains surrogates.
@@ -568,6 +921,27 @@ impl Wtf8 {
         EncodeWide { code_points: self.code_points(), extra: 0 }
     }
 
+    /// Returns the exact number of 16-bit code units `encode_wide` yields.
+    ///
+    /// Code points outside the BMP take two code units, every other code
+    /// point (including lone surrogates) takes one.
+    #[inline]
+    pub fn encoded_wide_len(&self) -> usize {
+        self.bytes.iter().map(|&b| match b {
+            0x80...0xBF => 0,
+            0xF0...0xFF => 2,
+            _ => 1,
+        }).sum()
+    }
+
+    /// Appends the potentially ill-formed UTF-16 encoding of the string
+    /// to `buf`, reserving the required space up front.
+    #[inline]
+    pub fn encode_wide_into(&self, buf: &mut Vec<u16>) {
+        buf.reserve(self.encoded_wide_len());
+        buf.extend(self.encode_wide());
+    }
+
     #[inline]
     fn next_surrogate(&self, mut pos: usize) -> Option<(usize, u16)> {
         let mut iter = self.bytes[pos..].iter();
@@ -601,6 +975,40 @@ impl Wtf8 {
         }
     }
 
+    /// Returns the byte position where the last code point starts.
+    #[inline]
+    fn last_code_point_start(&self) -> Option<usize> {
+        self.bytes.iter().rposition(|&b| b < 0x80 || b >= 0xC0)
+    }
+
+    /// Returns the byte position where the code point starting at `pos` ends,
+    /// or `None` if `pos` is the end of the string.
+    #[inline]
+    fn next_code_point_boundary(&self, pos: usize) -> Option<usize> {
+        if pos == self.len() {
+            return None
+        }
+        let continuation_bytes = self.bytes[pos + 1..].iter()
+                                                      .take_while(|&&b| b & 0xC0 == 0x80)
+                                                      .count();
+        Some(pos + 1 + continuation_bytes)
+    }
+
+    /// Returns whether the string starts with a low (trail) surrogate.
+    ///
+    /// Appending such a string to one which `ends_with_high_surrogate` joins
+    /// the two surrogates into a single supplementary code point.
+    #[inline]
+    pub fn starts_with_low_surrogate(&self) -> bool {
+        self.initial_trail_surrogate().is_some()
+    }
+
+    /// Returns whether the string ends with a high (lead) surrogate.
+    #[inline]
+    pub fn ends_with_high_surrogate(&self) -> bool {
+        self.final_lead_surrogate().is_some()
+    }
+
     #[inline]
     fn final_lead_surrogate(&self) -> Option<u16> {
         let len = self.len();
@@ -625,6 +1033,22 @@ impl Wtf8 {
         }
     }
 
+    /// Returns the slice of the string at `index`, or `None` if it is out of
+    /// bounds or does not start and end on code point boundaries.
+    #[inline]
+    pub fn get<I: Wtf8Index>(&self, index: I) -> Option<&Wtf8> {
+        index.get(self)
+    }
+
+    /// Returns the slice of the string at `index` without any checks.
+    ///
+    /// The caller must ensure that the range is in bounds and starts and ends
+    /// on code point boundaries.
+    #[inline]
+    pub unsafe fn get_unchecked<I: Wtf8Index>(&self, index: I) -> &Wtf8 {
+        index.get_unchecked(self)
+    }
+
     /// Boxes this `Wtf8`.
     #[inline]
     pub fn into_box(&self) -> Box<Wtf8> {
@@ -640,74 +1064,176 @@ impl Wtf8 {
 }
 
 
-/// Return a slice of the given string for the byte range [`begin`..`end`).
+/// A range of byte offsets which can be used to slice a `Wtf8`.
+///
+/// This plays the role `SliceIndex` plays for `str`: both indexing with `[]`
+/// and `Wtf8::get` are defined in terms of it, so every range type supports
+/// panicking, checked and unchecked slicing alike.
+pub trait Wtf8Index {
+    /// Returns the slice of `slice` at this range, or `None` if the range is
+    /// out of bounds or does not start and end on code point boundaries.
+    fn get(self, slice: &Wtf8) -> Option<&Wtf8>;
+
+    /// Returns the slice of `slice` at this range without any checks.
+    unsafe fn get_unchecked(self, slice: &Wtf8) -> &Wtf8;
+
+    /// Returns the slice of `slice` at this range, panicking if it is out of
+    /// bounds or does not start and end on code point boundaries.
+    fn index(self, slice: &Wtf8) -> &Wtf8;
+}
+
+/// Slices the given string for the byte range [`begin`..`end`).
 ///
 /// # Panics
 ///
 /// Panics when `begin` and `end` do not point to code point boundaries,
 /// or point beyond the end of the string.
-impl ops::Index<ops::Range<usize>> for Wtf8 {
-    type Output = Wtf8;
-
+impl Wtf8Index for ops::Range<usize> {
     #[inline]
-    fn index(&self, range: ops::Range<usize>) -> &Wtf8 {
+    fn get(self, slice: &Wtf8) -> Option<&Wtf8> {
         // is_code_point_boundary checks that the index is in [0, .len()]
-        if range.start <= range.end &&
-           is_code_point_boundary(self, range.start) &&
-           is_code_point_boundary(self, range.end) {
-            unsafe { slice_unchecked(self, range.start, range.end) }
+        if self.start <= self.end &&
+           is_code_point_boundary(slice, self.start) &&
+           is_code_point_boundary(slice, self.end) {
+            Some(unsafe { self.get_unchecked(slice) })
         } else {
-            slice_error_fail(self, range.start, range.end)
+            None
         }
     }
+
+    #[inline]
+    unsafe fn get_unchecked(self, slice: &Wtf8) -> &Wtf8 {
+        slice_unchecked(slice, self.start, self.end)
+    }
+
+    #[inline]
+    fn index(self, slice: &Wtf8) -> &Wtf8 {
+        let (start, end) = (self.start, self.end);
+        self.get(slice).unwrap_or_else(|| slice_error_fail(slice, start, end))
+    }
 }
 
-/// Return a slice of the given string from byte `begin` to its end.
+/// Slices the given string from byte `begin` to its end.
 ///
 /// # Panics
 ///
 /// Panics when `begin` is not at a code point boundary,
 /// or is beyond the end of the string.
-impl ops::Index<ops::RangeFrom<usize>> for Wtf8 {
-    type Output = Wtf8;
+impl Wtf8Index for ops::RangeFrom<usize> {
+    #[inline]
+    fn get(self, slice: &Wtf8) -> Option<&Wtf8> {
+        (self.start..slice.len()).get(slice)
+    }
 
     #[inline]
-    fn index(&self, range: ops::RangeFrom<usize>) -> &Wtf8 {
-        // is_code_point_boundary checks that the index is in [0, .len()]
-        if is_code_point_boundary(self, range.start) {
-            unsafe { slice_unchecked(self, range.start, self.len()) }
-        } else {
-            slice_error_fail(self, range.start, self.len())
-        }
+    unsafe fn get_unchecked(self, slice: &Wtf8) -> &Wtf8 {
+        slice_unchecked(slice, self.start, slice.len())
+    }
+
+    #[inline]
+    fn index(self, slice: &Wtf8) -> &Wtf8 {
+        (self.start..slice.len()).index(slice)
     }
 }
 
-/// Return a slice of the given string from its beginning to byte `end`.
+/// Slices the given string from its beginning to byte `end`.
 ///
 /// # Panics
 ///
 /// Panics when `end` is not at a code point boundary,
 /// or is beyond the end of the string.
-impl ops::Index<ops::RangeTo<usize>> for Wtf8 {
-    type Output = Wtf8;
+impl Wtf8Index for ops::RangeTo<usize> {
+    #[inline]
+    fn get(self, slice: &Wtf8) -> Option<&Wtf8> {
+        (0..self.end).get(slice)
+    }
 
     #[inline]
-    fn index(&self, range: ops::RangeTo<usize>) -> &Wtf8 {
-        // is_code_point_boundary checks that the index is in [0, .len()]
-        if is_code_point_boundary(self, range.end) {
-            unsafe { slice_unchecked(self, 0, range.end) }
-        } else {
-            slice_error_fail(self, 0, range.end)
+    unsafe fn get_unchecked(self, slice: &Wtf8) -> &Wtf8 {
+        slice_unchecked(slice, 0, self.end)
+    }
+
+    #[inline]
+    fn index(self, slice: &Wtf8) -> &Wtf8 {
+        (0..self.end).index(slice)
+    }
+}
+
+/// Slices the given string for the byte range [`begin`..`end`].
+///
+/// # Panics
+///
+/// Panics when `begin` does not point to a code point boundary, when `end`
+/// does not point to the last byte of a code point, or when either points
+/// beyond the end of the string.
+impl Wtf8Index for ops::RangeInclusive<usize> {
+    #[inline]
+    fn get(self, slice: &Wtf8) -> Option<&Wtf8> {
+        match self.end.checked_add(1) {
+            Some(end) => (self.start..end).get(slice),
+            None => None,
         }
     }
+
+    #[inline]
+    unsafe fn get_unchecked(self, slice: &Wtf8) -> &Wtf8 {
+        slice_unchecked(slice, self.start, self.end + 1)
+    }
+
+    #[inline]
+    fn index(self, slice: &Wtf8) -> &Wtf8 {
+        assert!(self.end != usize::max_value(),
+                "attempted to index Wtf8 up to maximum usize");
+        (self.start..self.end + 1).index(slice)
+    }
+}
+
+/// Slices the given string from its beginning to byte `end` inclusive.
+///
+/// # Panics
+///
+/// Panics when `end` does not point to the last byte of a code point,
+/// or is beyond the end of the string.
+impl Wtf8Index for ops::RangeToInclusive<usize> {
+    #[inline]
+    fn get(self, slice: &Wtf8) -> Option<&Wtf8> {
+        ops::RangeInclusive { start: 0, end: self.end }.get(slice)
+    }
+
+    #[inline]
+    unsafe fn get_unchecked(self, slice: &Wtf8) -> &Wtf8 {
+        slice_unchecked(slice, 0, self.end + 1)
+    }
+
+    #[inline]
+    fn index(self, slice: &Wtf8) -> &Wtf8 {
+        ops::RangeInclusive { start: 0, end: self.end }.index(slice)
+    }
 }
 
-impl ops::Index<ops::RangeFull> for Wtf8 {
+impl Wtf8Index for ops::RangeFull {
+    #[inline]
+    fn get(self, slice: &Wtf8) -> Option<&Wtf8> {
+        Some(slice)
+    }
+
+    #[inline]
+    unsafe fn get_unchecked(self, slice: &Wtf8) -> &Wtf8 {
+        slice
+    }
+
+    #[inline]
+    fn index(self, slice: &Wtf8) -> &Wtf8 {
+        slice
+    }
+}
+
+impl<I: Wtf8Index> ops::Index<I> for Wtf8 {
     type Output = Wtf8;
 
     #[inline]
-    fn index(&self, _range: ops::RangeFull) -> &Wtf8 {
-        self
+    fn index(&self, index: I) -> &Wtf8 {
+        index.index(self)
     }
 }
 
@@ -774,12 +1300,205 @@ impl<'a> Iterator for Wtf8CodePoints<'a> {
     }
 }
 
-/// Generates a wide character sequence for potentially ill-formed UTF-16.
-#[stable(feature = "rust1", since = "1.0.0")]
+/// Iterator for the chars of a WTF-8 string, with unpaired surrogates
+/// replaced by U+FFFD REPLACEMENT CHARACTER.
+///
+/// Created with the method `.chars_lossy()`.
 #[derive(Clone)]
-pub struct EncodeWide<'a> {
+pub struct CharsLossy<'a> {
     code_points: Wtf8CodePoints<'a>,
-    extra: u16
+}
+
+impl<'a> Iterator for CharsLossy<'a> {
+    type Item = char;
+
+    #[inline]
+    fn next(&mut self) -> Option<char> {
+        self.code_points.next().map(|c| c.to_char_lossy())
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.code_points.size_hint()
+    }
+}
+
+/// Iterator for the chars of a WTF-8 string and their byte positions, with
+/// unpaired surrogates replaced by U+FFFD REPLACEMENT CHARACTER.
+///
+/// Created with the method `.char_indices_lossy()`.
+#[derive(Clone)]
+pub struct CharIndicesLossy<'a> {
+    front_offset: usize,
+    code_points: Wtf8CodePoints<'a>,
+}
+
+impl<'a> Iterator for CharIndicesLossy<'a> {
+    type Item = (usize, char);
+
+    #[inline]
+    fn next(&mut self) -> Option<(usize, char)> {
+        let pre_len = self.code_points.bytes.len();
+        match self.code_points.next() {
+            Some(c) => {
+                let index = self.front_offset;
+                self.front_offset += pre_len - self.code_points.bytes.len();
+                Some((index, c.to_char_lossy()))
+            }
+            None => None,
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.code_points.size_hint()
+    }
+}
+
+/// Iterator over overlapping windows of code points of a WTF-8 string.
+///
+/// Created with the method `.code_point_windows()`.
+#[derive(Clone)]
+pub struct CodePointWindows<'a> {
+    wtf8: &'a Wtf8,
+    start: usize,
+    end: Option<usize>,
+}
+
+impl<'a> Iterator for CodePointWindows<'a> {
+    type Item = &'a Wtf8;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a Wtf8> {
+        let end = match self.end {
+            Some(end) => end,
+            None => return None,
+        };
+        // Both ends are always at code point boundaries.
+        let window = unsafe { slice_unchecked(self.wtf8, self.start, end) };
+        self.start = self.wtf8.next_code_point_boundary(self.start).unwrap();
+        self.end = self.wtf8.next_code_point_boundary(end);
+        Some(window)
+    }
+}
+
+/// Iterator over the chunks of a WTF-8 string, split at unpaired surrogates.
+///
+/// Created with the method `.chunks()`.
+#[derive(Clone)]
+pub struct Wtf8Chunks<'a> {
+    rest: &'a [u8],
+}
+
+/// A chunk of a WTF-8 string: valid UTF-8 followed by an unpaired surrogate.
+#[derive(PartialEq, Eq, Debug)]
+pub struct Wtf8Chunk<'a> {
+    /// Sequence of valid chars.
+    /// Can be empty between surrogates.
+    pub valid: &'a str,
+    /// The unpaired surrogate following `valid`.
+    /// `None` only if this is the last chunk.
+    pub surrogate: Option<u16>,
+}
+
+impl<'a> Iterator for Wtf8Chunks<'a> {
+    type Item = Wtf8Chunk<'a>;
+
+    fn next(&mut self) -> Option<Wtf8Chunk<'a>> {
+        if self.rest.is_empty() {
+            return None
+        }
+        let rest = self.rest;
+        let wtf8 = unsafe { Wtf8::from_bytes_unchecked(rest) };
+        let (valid_len, surrogate) = match wtf8.next_surrogate(0) {
+            Some((pos, surrogate)) => {
+                self.rest = &rest[pos + 3..];
+                (pos, Some(surrogate))
+            }
+            None => {
+                self.rest = &[];
+                (rest.len(), None)
+            }
+        };
+        Some(Wtf8Chunk {
+            valid: unsafe { str::from_utf8_unchecked(&rest[..valid_len]) },
+            surrogate: surrogate,
+        })
+    }
+}
+
+/// Iterator over the lines of a WTF-8 string.
+///
+/// Created with the method `.lines()`.
+#[derive(Clone)]
+pub struct Wtf8Lines<'a> {
+    rest: &'a [u8],
+}
+
+impl<'a> Iterator for Wtf8Lines<'a> {
+    type Item = &'a Wtf8;
+
+    fn next(&mut self) -> Option<&'a Wtf8> {
+        if self.rest.is_empty() {
+            return None
+        }
+        // `\n` and `\r` never occur within a multi-byte sequence, so this
+        // always splits at code point boundaries.
+        let mut line = match self.rest.iter().position(|&b| b == b'\n') {
+            Some(pos) => {
+                let line = &self.rest[..pos];
+                self.rest = &self.rest[pos + 1..];
+                line
+            }
+            None => mem::replace(&mut self.rest, &[]),
+        };
+        if line.last() == Some(&b'\r') {
+            line = &line[..line.len() - 1];
+        }
+        Some(unsafe { Wtf8::from_bytes_unchecked(line) })
+    }
+}
+
+/// Iterator over the substrings of a WTF-8 string separated by ASCII
+/// whitespace.
+///
+/// Created with the method `.split_ascii_whitespace()`.
+#[derive(Clone)]
+pub struct Wtf8SplitAsciiWhitespace<'a> {
+    rest: &'a [u8],
+}
+
+fn is_ascii_whitespace(b: u8) -> bool {
+    match b {
+        b'\t' | b'\n' | b'\x0C' | b'\r' | b' ' => true,
+        _ => false,
+    }
+}
+
+impl<'a> Iterator for Wtf8SplitAsciiWhitespace<'a> {
+    type Item = &'a Wtf8;
+
+    fn next(&mut self) -> Option<&'a Wtf8> {
+        let start = match self.rest.iter().position(|&b| !is_ascii_whitespace(b)) {
+            Some(start) => start,
+            None => {
+                self.rest = &[];
+                return None
+            }
+        };
+        let rest = &self.rest[start..];
+        let end = rest.iter().position(|&b| is_ascii_whitespace(b)).unwrap_or(rest.len());
+        self.rest = &rest[end..];
+        Some(unsafe { Wtf8::from_bytes_unchecked(&rest[..end]) })
+    }
+}
+
+/// Generates a wide character sequence for potentially ill-formed UTF-16.
+#[stable(feature = "rust1", since = "1.0.0")]
+#[derive(Clone)]
+pub struct EncodeWide<'a> {
+    code_points: Wtf8CodePoints<'a>,
+    extra: u16
 }
 
 // Copied from libunicode/u_str.rs
@@ -818,6 +1537,171 @@ impl<'a> Iterator for EncodeWide<'a> {
     }
 }
 
+fn escape_debug_char(c: char) -> char::EscapeDebug { c.escape_debug() }
+fn escape_default_char(c: char) -> char::EscapeDefault { c.escape_default() }
+
+/// Shared implementation of `EscapeDebug` and `EscapeDefault`,
+/// escaping chars with `escape_char` and surrogates as `\u{dXXX}`.
+#[derive(Clone)]
+struct Escape<'a, E> {
+    code_points: Wtf8CodePoints<'a>,
+    escape_char: fn(char) -> E,
+    state: EscapeState<E>,
+}
+
+#[derive(Clone)]
+enum EscapeState<E> {
+    Idle,
+    Char(E),
+    Surrogate([u8; 8], usize),
+}
+
+impl<'a, E> Escape<'a, E> {
+    fn new(wtf8: &'a Wtf8, escape_char: fn(char) -> E) -> Escape<'a, E> {
+        Escape {
+            code_points: wtf8.code_points(),
+            escape_char: escape_char,
+            state: EscapeState::Idle,
+        }
+    }
+}
+
+impl<'a, E: Iterator<Item=char>> Iterator for Escape<'a, E> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        loop {
+            match self.state {
+                EscapeState::Idle => {}
+                EscapeState::Char(ref mut escape) => {
+                    if let Some(c) = escape.next() {
+                        return Some(c)
+                    }
+                }
+                EscapeState::Surrogate(ref bytes, ref mut pos) => {
+                    if *pos < bytes.len() {
+                        *pos += 1;
+                        return Some(bytes[*pos - 1] as char)
+                    }
+                }
+            }
+            let code_point = match self.code_points.next() {
+                None => return None,
+                Some(code_point) => code_point,
+            };
+            self.state = match code_point.to_char() {
+                Some(c) => EscapeState::Char((self.escape_char)(c)),
+                None => {
+                    const HEX: &'static [u8; 16] = b"0123456789abcdef";
+                    let mut bytes = *b"\\u{0000}";
+                    for i in 0..4 {
+                        bytes[3 + i] = HEX[(code_point.value >> (12 - 4 * i)) as usize & 0xF];
+                    }
+                    EscapeState::Surrogate(bytes, 0)
+                }
+            };
+        }
+    }
+}
+
+/// An iterator over the chars of a WTF-8 string, escaped as for `Debug`.
+///
+/// Created with the method `.escape_debug()`.
+#[derive(Clone)]
+pub struct EscapeDebug<'a> {
+    inner: Escape<'a, char::EscapeDebug>,
+}
+
+impl<'a> Iterator for EscapeDebug<'a> {
+    type Item = char;
+
+    #[inline]
+    fn next(&mut self) -> Option<char> {
+        self.inner.next()
+    }
+}
+
+impl<'a> fmt::Display for EscapeDebug<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use fmt::Write;
+        for c in self.clone() {
+            f.write_char(c)?
+        }
+        Ok(())
+    }
+}
+
+/// An iterator over the chars of a WTF-8 string,
+/// escaped with `char::escape_default`.
+///
+/// Created with the method `.escape_default()`.
+#[derive(Clone)]
+pub struct EscapeDefault<'a> {
+    inner: Escape<'a, char::EscapeDefault>,
+}
+
+impl<'a> Iterator for EscapeDefault<'a> {
+    type Item = char;
+
+    #[inline]
+    fn next(&mut self) -> Option<char> {
+        self.inner.next()
+    }
+}
+
+impl<'a> fmt::Display for EscapeDefault<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use fmt::Write;
+        for c in self.clone() {
+            f.write_char(c)?
+        }
+        Ok(())
+    }
+}
+
+// Comparisons with UTF-8 strings.
+//
+// Comparing the encoded bytes is exact: a surrogate pair is always encoded as
+// the supplementary code point it stands for, so equal strings have equal
+// bytes. Like the comparison of two `Wtf8`s it orders strings by code point,
+// as the encoding of surrogates sorts between those of U+D7FF and U+E000.
+
+macro_rules! impl_cmp_str {
+    ($wtf8:ty, $str:ty) => {
+        impl<'a> PartialEq<$str> for $wtf8 {
+            #[inline]
+            fn eq(&self, other: &$str) -> bool {
+                self.as_encoded_bytes() == other.as_bytes()
+            }
+        }
+
+        impl<'a> PartialEq<$wtf8> for $str {
+            #[inline]
+            fn eq(&self, other: &$wtf8) -> bool {
+                self.as_bytes() == other.as_encoded_bytes()
+            }
+        }
+
+        impl<'a> PartialOrd<$str> for $wtf8 {
+            #[inline]
+            fn partial_cmp(&self, other: &$str) -> Option<cmp::Ordering> {
+                self.as_encoded_bytes().partial_cmp(other.as_bytes())
+            }
+        }
+
+        impl<'a> PartialOrd<$wtf8> for $str {
+            #[inline]
+            fn partial_cmp(&self, other: &$wtf8) -> Option<cmp::Ordering> {
+                self.as_bytes().partial_cmp(other.as_encoded_bytes())
+            }
+        }
+    }
+}
+
+impl_cmp_str!(Wtf8, str);
+impl_cmp_str!(Wtf8Buf, str);
+impl_cmp_str!(Wtf8Buf, &'a str);
+
 impl Hash for CodePoint {
     #[inline]
     fn hash<H: Hasher>(&self, state: &mut H) {
@@ -864,6 +1748,7 @@ impl AsciiExt for Wtf8 {
 #[cfg(test)]
 mod tests {
     use borrow::Cow;
+    use test;
     use super::*;
 
     #[test]
@@ -998,6 +1883,34 @@ mod tests {
         assert_eq!(string.bytes, b"\xED\xB0\x80");
     }
 
+    #[bench]
+    fn bench_push_wtf8_small_pieces(b: &mut test::Bencher) {
+        let piece = Wtf8::from_str("path");
+        b.iter(|| {
+            let mut string = Wtf8Buf::new();
+            for _ in 0..1000 {
+                string.push_wtf8(piece);
+            }
+            test::black_box(&string);
+        })
+    }
+
+    #[bench]
+    fn bench_push_wtf8_surrogate_pairs(b: &mut test::Bencher) {
+        let mut lead = Wtf8Buf::from_str("path");
+        lead.push(CodePoint::from_u32(0xD83D).unwrap());
+        let mut trail = Wtf8Buf::new();
+        trail.push(CodePoint::from_u32(0xDCA9).unwrap());
+        b.iter(|| {
+            let mut string = Wtf8Buf::new();
+            for _ in 0..500 {
+                string.push_wtf8(&lead);
+                string.push_wtf8(&trail);
+            }
+            test::black_box(&string);
+        })
+    }
+
     #[test]
     fn wtf8buf_push_wtf8() {
         let mut string = Wtf8Buf::from_str("aé");
@@ -1043,6 +1956,20 @@ mod tests {
         assert_eq!(string.bytes, b"\xED\xB0\x80");
     }
 
+    #[test]
+    fn wtf8buf_extend_with_char() {
+        let mut string = Wtf8Buf::from_str("a");
+        string.extend_with_char(' ', 3);
+        string.extend_with_char('💩', 2);
+        string.extend_with_char('é', 0);
+        assert_eq!(string.bytes, b"a   \xF0\x9F\x92\xA9\xF0\x9F\x92\xA9");
+
+        let mut string = Wtf8Buf::new();
+        string.push(CodePoint::from_u32(0xD83D).unwrap());
+        string.extend_with_char('a', 1);
+        assert_eq!(string.bytes, b"\xED\xA0\xBDa");
+    }
+
     #[test]
     fn wtf8buf_truncate() {
         let mut string = Wtf8Buf::from_str("aé");
@@ -1064,6 +1991,35 @@ mod tests {
         string.truncate(4);
     }
 
+    #[test]
+    fn wtf8buf_pop() {
+        fn c(value: u32) -> CodePoint { CodePoint::from_u32(value).unwrap() }
+
+        let mut string = Wtf8Buf::from_str("aé 💩");
+        assert_eq!(string.pop(), Some(c(0x1F4A9)));
+        assert_eq!(string.bytes, b"a\xC3\xA9 ");
+        assert_eq!(string.pop(), Some(c(0x20)));
+        assert_eq!(string.pop(), Some(c(0xE9)));
+        assert_eq!(string.pop(), Some(c(0x61)));
+        assert_eq!(string.pop(), None);
+        assert_eq!(string.bytes, b"");
+
+        let mut string = Wtf8Buf::new();
+        string.push(c(0xD83D));  // lead
+        string.push(c(0xDCA9));  // trail
+        assert_eq!(string.pop(), Some(c(0x1F4A9)));  // Magic!
+        assert_eq!(string.bytes, b"");
+
+        let mut string = Wtf8Buf::new();
+        string.push(c(0xD83D));  // lead
+        string.push(c(0x20));  // not surrogate
+        string.push(c(0xDCA9));  // trail
+        assert_eq!(string.pop(), Some(c(0xDCA9)));
+        assert_eq!(string.pop(), Some(c(0x20)));
+        assert_eq!(string.pop(), Some(c(0xD83D)));
+        assert_eq!(string.pop(), None);
+    }
+
     #[test]
     fn wtf8buf_into_string() {
         let mut string = Wtf8Buf::from_str("aé 💩");
@@ -1080,6 +2036,50 @@ mod tests {
         assert_eq!(string.clone().into_string_lossy(), String::from("aé 💩�"));
     }
 
+    #[test]
+    fn wtf8buf_into_string_with() {
+        let escape = |s: &Wtf8, out: &mut String| {
+            let c = s.code_points().next().unwrap();
+            out.push_str(&format!("\\u{{{:X}}}", c.to_u32()));
+        };
+        let mut string = Wtf8Buf::from_str("aé 💩");
+        assert_eq!(string.clone().into_string_with(&escape), String::from("aé 💩"));
+        string.push(CodePoint::from_u32(0xD800).unwrap());
+        string.push_str("b");
+        string.push(CodePoint::from_u32(0xDFFF).unwrap());
+        assert_eq!(string.clone().into_string_with(&escape),
+                   String::from("aé 💩\\u{D800}b\\u{DFFF}"));
+        assert_eq!(string.clone().into_string_with(|_, _| ()), String::from("aé 💩b"));
+        let mut count = 0;
+        string.into_string_with(|s, _| { assert_eq!(s.len(), 3); count += 1; });
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn wtf8buf_into_string_with_errors() {
+        let mut string = Wtf8Buf::from_str("a");
+        string.push(CodePoint::from_u32(0xD800).unwrap());
+        string.push_str("é");
+        string.push(CodePoint::from_u32(0xDBFF).unwrap());
+        // Record where each surrogate was found, and mark it in the output.
+        let mut errors = Vec::new();
+        let converted = string.into_string_with(|s, out| {
+            errors.push((out.len(), s.code_points().next().unwrap().to_u32()));
+            out.push('?');
+        });
+        assert_eq!(converted, "a?é?");
+        assert_eq!(errors, [(1, 0xD800), (4, 0xDBFF)]);
+    }
+
+    #[test]
+    fn wtf8buf_encoded_bytes() {
+        let mut string = Wtf8Buf::from_str("aé");
+        string.push(CodePoint::from_u32(0xD800).unwrap());
+        let bytes = string.clone().into_encoded_bytes();
+        assert_eq!(bytes, b"a\xC3\xA9\xED\xA0\x80");
+        assert_eq!(unsafe { Wtf8Buf::from_encoded_bytes_unchecked(bytes) }, string);
+    }
+
     #[test]
     fn wtf8buf_from_iterator() {
         fn f(values: &[u32]) -> Wtf8Buf {
@@ -1096,6 +2096,41 @@ mod tests {
         assert_eq!(f(&[0xDC00]).bytes, b"\xED\xB0\x80");
     }
 
+    #[test]
+    fn wtf8buf_from_iterator_u16() {
+        fn f(units: &[u16]) -> Vec<u8> {
+            units.iter().cloned().collect::<Wtf8Buf>().bytes
+        }
+
+        assert_eq!(f(&[0x61, 0xE9, 0x20, 0xD83D, 0xDCA9]), b"a\xC3\xA9 \xF0\x9F\x92\xA9");
+        assert_eq!(f(&[0xD83D, 0x20, 0xDCA9]), b"\xED\xA0\xBD \xED\xB2\xA9");
+        assert_eq!(f(&[0xD800, 0xDBFF]), b"\xED\xA0\x80\xED\xAF\xBF");
+        assert_eq!(f(&[0xDC00, 0xD800]), b"\xED\xB0\x80\xED\xA0\x80");
+        assert_eq!(f(&[]), b"");
+    }
+
+    #[test]
+    fn wtf16_decoder() {
+        let mut decoder = Wtf16Decoder::new();
+        decoder.push_slice(&[0x61, 0xD83D]);
+        assert_eq!(&decoder.as_wtf8().bytes, b"a\xED\xA0\xBD");
+        decoder.push_slice(&[0xDCA9, 0xD800]);  // pair split across chunks
+        assert_eq!(&decoder.as_wtf8().bytes, b"a\xF0\x9F\x92\xA9\xED\xA0\x80");
+        decoder.push(0x20);
+        decoder.push(0xDC00);
+        decoder.push_slice(&[]);
+        assert_eq!(decoder.finish().bytes, b"a\xF0\x9F\x92\xA9\xED\xA0\x80 \xED\xB0\x80");
+
+        // every split of a string gives the same result as decoding it at once
+        let units = [0x61, 0xD83D, 0xDCA9, 0xDCA9, 0xD83D, 0xD83D, 0xDCA9, 0xD800];
+        for i in 0..units.len() + 1 {
+            let mut decoder = Wtf16Decoder::new();
+            decoder.push_slice(&units[..i]);
+            decoder.push_slice(&units[i..]);
+            assert_eq!(decoder.finish(), Wtf8Buf::from_wide(&units));
+        }
+    }
+
     #[test]
     fn wtf8buf_extend() {
         fn e(initial: &[u32], extended: &[u32]) -> Wtf8Buf {
@@ -1117,6 +2152,50 @@ mod tests {
         assert_eq!(e(&[], &[0xDC00]).bytes, b"\xED\xB0\x80");
     }
 
+    #[test]
+    fn wtf8buf_add() {
+        fn w(v: &[u8]) -> &Wtf8 { unsafe { Wtf8::from_bytes_unchecked(v) } }
+        fn a(initial: &[u8], added: &[u8]) -> Vec<u8> {
+            let sum = (Wtf8Buf { bytes: initial.to_vec() } + w(added)).bytes;
+            let mut string = Wtf8Buf { bytes: initial.to_vec() };
+            string += w(added);
+            assert_eq!(string.bytes, sum);
+            sum
+        }
+
+        assert_eq!(a(b"a\xC3\xA9", b" \xF0\x9F\x92\xA9"), b"a\xC3\xA9 \xF0\x9F\x92\xA9");
+        assert_eq!(a(b"\xED\xA0\xBD", b"\xED\xB2\xA9"), b"\xF0\x9F\x92\xA9");  // Magic!
+        assert_eq!(a(b"\xED\xA0\xBD", b"\xED\xB2\xA9 "), b"\xF0\x9F\x92\xA9 ");  // Magic!
+        assert_eq!(a(b"\xED\xA0\xBD ", b"\xED\xB2\xA9"), b"\xED\xA0\xBD \xED\xB2\xA9");
+        assert_eq!(a(b"\xED\xA0\x80", b"\xED\xAF\xBF"), b"\xED\xA0\x80\xED\xAF\xBF");
+        assert_eq!(a(b"\xED\xA0\x80", b"\xEE\x80\x80"), b"\xED\xA0\x80\xEE\x80\x80");
+        assert_eq!(a(b"\xED\x9F\xBF", b"\xED\xB0\x80"), b"\xED\x9F\xBF\xED\xB0\x80");
+        assert_eq!(a(b"", b"\xED\xB0\x80"), b"\xED\xB0\x80");
+        assert_eq!(a(b"\xED\xA0\x80", b""), b"\xED\xA0\x80");
+    }
+
+    #[test]
+    fn wtf8buf_extend_wtf8() {
+        fn w(v: &[u8]) -> &Wtf8 { unsafe { Wtf8::from_bytes_unchecked(v) } }
+        fn e(initial: &[u8], extended: &[&[u8]]) -> Vec<u8> {
+            let mut string = Wtf8Buf { bytes: initial.to_vec() };
+            string.extend(extended.iter().map(|v| w(v)));
+            string.bytes
+        }
+
+        assert_eq!(e(b"a", &[b"\xC3\xA9", b" ", b"\xF0\x9F\x92\xA9"]),
+                   b"a\xC3\xA9 \xF0\x9F\x92\xA9");
+        assert_eq!(e(b"\xED\xA0\xBD", &[b"\xED\xB2\xA9"]), b"\xF0\x9F\x92\xA9");  // Magic!
+        assert_eq!(e(b"", &[b"\xED\xA0\xBD", b"\xED\xB2\xA9"]), b"\xF0\x9F\x92\xA9");  // Magic!
+        assert_eq!(e(b"", &[b"\xED\xA0\xBD", b"", b"\xED\xB2\xA9"]),
+                   b"\xF0\x9F\x92\xA9");  // Magic!
+        assert_eq!(e(b"", &[b"\xED\xA0\xBD", b" ", b"\xED\xB2\xA9"]),
+                   b"\xED\xA0\xBD \xED\xB2\xA9");
+        assert_eq!(e(b"\xED\xA0\x80", &[b"\xED\xAF\xBF"]), b"\xED\xA0\x80\xED\xAF\xBF");
+        assert_eq!(e(b"\xED\xB0\x80", &[b"\xED\xA0\x80"]), b"\xED\xB0\x80\xED\xA0\x80");
+        assert_eq!(e(b"\xED\xA0\x80", &[]), b"\xED\xA0\x80");
+    }
+
     #[test]
     fn wtf8buf_show() {
         let mut string = Wtf8Buf::from_str("a\té \u{7f}💩\r");
@@ -1136,6 +2215,192 @@ mod tests {
         assert_eq!(format!("{:?}", text), format!("{:?}", string));
     }
 
+    #[test]
+    fn wtf8_escape_debug() {
+        let mut string = Wtf8Buf::from_str("a\té \"💩\r");
+        string.push(CodePoint::from_u32(0xD800).unwrap());
+        string.push_str("\n");
+        string.push(CodePoint::from_u32(0xDFFF).unwrap());
+        let escaped = "a\\té \\\"💩\\r\\u{d800}\\n\\u{dfff}";
+        assert_eq!(string.escape_debug().collect::<String>(), escaped);
+        assert_eq!(string.escape_debug().to_string(), escaped);
+        assert_eq!(Wtf8::from_str("").escape_debug().next(), None);
+    }
+
+    #[test]
+    fn wtf8_escape_default() {
+        let mut string = Wtf8Buf::from_str("a\té \"💩\r");
+        string.push(CodePoint::from_u32(0xDBFF).unwrap());
+        let escaped = "a\\t\\u{e9} \\\"\\u{1f4a9}\\r\\u{dbff}";
+        assert_eq!(string.escape_default().collect::<String>(), escaped);
+        assert_eq!(string.escape_default().to_string(), escaped);
+    }
+
+    #[test]
+    fn wtf8_encoded_bytes() {
+        let mut string = Wtf8Buf::from_str("aé");
+        string.push(CodePoint::from_u32(0xD800).unwrap());
+        let bytes = string.as_encoded_bytes();
+        assert_eq!(bytes, b"a\xC3\xA9\xED\xA0\x80");
+        assert_eq!(unsafe { Wtf8::from_encoded_bytes_unchecked(bytes) }, &*string);
+        assert_eq!(unsafe { Wtf8::from_encoded_bytes_unchecked(&bytes[1..3]) },
+                   Wtf8::from_str("é"));
+    }
+
+    #[test]
+    fn wtf8_repeat() {
+        fn w(v: &[u8]) -> &Wtf8 { unsafe { Wtf8::from_bytes_unchecked(v) } }
+
+        assert_eq!(Wtf8::from_str("aé").repeat(3).bytes, b"a\xC3\xA9a\xC3\xA9a\xC3\xA9");
+        assert_eq!(Wtf8::from_str("aé").repeat(0).bytes, b"");
+        assert_eq!(Wtf8::from_str("").repeat(5).bytes, b"");
+
+        // trail + lead: the lead of one copy pairs with the trail of the next
+        assert_eq!(w(b"\xED\xB2\xA9\xED\xA0\xBD").repeat(1).bytes,
+                   b"\xED\xB2\xA9\xED\xA0\xBD");
+        assert_eq!(w(b"\xED\xB2\xA9\xED\xA0\xBD").repeat(3).bytes,
+                   b"\xED\xB2\xA9\xF0\x9F\x92\xA9\xF0\x9F\x92\xA9\xED\xA0\xBD");  // Magic!
+        assert_eq!(w(b"\xED\xB2\xA9a\xED\xA0\xBD").repeat(2).bytes,
+                   b"\xED\xB2\xA9a\xF0\x9F\x92\xA9a\xED\xA0\xBD");  // Magic!
+
+        // lone surrogates which don't pair up across copies
+        assert_eq!(w(b"\xED\xA0\xBD").repeat(2).bytes, b"\xED\xA0\xBD\xED\xA0\xBD");
+        assert_eq!(w(b"\xED\xB2\xA9").repeat(2).bytes, b"\xED\xB2\xA9\xED\xB2\xA9");
+        assert_eq!(w(b"\xED\xA0\xBDa\xED\xB2\xA9").repeat(2).bytes,
+                   b"\xED\xA0\xBDa\xED\xB2\xA9\xED\xA0\xBDa\xED\xB2\xA9");
+    }
+
+    #[test]
+    fn wtf8_chunks() {
+        fn c(valid: &str, surrogate: Option<u16>) -> Wtf8Chunk {
+            Wtf8Chunk { valid: valid, surrogate: surrogate }
+        }
+
+        assert_eq!(Wtf8::from_str("").chunks().next(), None);
+        assert_eq!(Wtf8::from_str("aé 💩").chunks().collect::<Vec<_>>(),
+                   vec![c("aé 💩", None)]);
+
+        let mut string = Wtf8Buf::from_str("aé");
+        string.push(CodePoint::from_u32(0xD83D).unwrap());
+        string.push(CodePoint::from_u32(0xD800).unwrap());
+        string.push_str("💩");
+        string.push(CodePoint::from_u32(0xDFFF).unwrap());
+        assert_eq!(string.chunks().collect::<Vec<_>>(),
+                   vec![c("aé", Some(0xD83D)), c("", Some(0xD800)), c("💩", Some(0xDFFF))]);
+
+        string.push_str("z");
+        assert_eq!(string.chunks().last(), Some(c("z", None)));
+    }
+
+    #[test]
+    fn wtf8_surrogate_ends() {
+        let mut string = Wtf8Buf::from_str("aé");
+        assert!(!string.starts_with_low_surrogate());
+        assert!(!string.ends_with_high_surrogate());
+
+        string.push(CodePoint::from_u32(0xD83D).unwrap());
+        assert!(!string.starts_with_low_surrogate());
+        assert!(string.ends_with_high_surrogate());
+
+        let mut string = Wtf8Buf::new();
+        string.push(CodePoint::from_u32(0xDCA9).unwrap());
+        string.push_str("💩");
+        assert!(string.starts_with_low_surrogate());
+        assert!(!string.ends_with_high_surrogate());
+        assert!(!string[3..].starts_with_low_surrogate());
+    }
+
+    #[test]
+    fn wtf8_lines() {
+        fn lines(string: &Wtf8) -> Vec<&[u8]> {
+            string.lines().map(|line| &line.bytes[..]).collect()
+        }
+
+        assert!(lines(Wtf8::from_str("")).is_empty());
+        assert_eq!(lines(Wtf8::from_str("\n")), [b""]);
+        assert_eq!(lines(Wtf8::from_str("a\r\n\nb\n")), [&b"a"[..], b"", b"b"]);
+        assert_eq!(lines(Wtf8::from_str("a\rb\r")), [b"a\rb"]);
+
+        let mut string = Wtf8Buf::from_str("é\r\n");
+        string.push(CodePoint::from_u32(0xD800).unwrap());
+        string.push_str("\n💩");
+        assert_eq!(lines(&string), [&b"\xC3\xA9"[..], b"\xED\xA0\x80", b"\xF0\x9F\x92\xA9"]);
+    }
+
+    #[test]
+    fn wtf8_split_ascii_whitespace() {
+        fn split(string: &Wtf8) -> Vec<&[u8]> {
+            string.split_ascii_whitespace().map(|word| &word.bytes[..]).collect()
+        }
+
+        assert!(split(Wtf8::from_str("")).is_empty());
+        assert!(split(Wtf8::from_str(" \t\r\n\x0C")).is_empty());
+        assert_eq!(split(Wtf8::from_str("  -j4 \t--foo\n")), [&b"-j4"[..], b"--foo"]);
+        // U+00A0 NO-BREAK SPACE isn't ASCII whitespace.
+        assert_eq!(split(Wtf8::from_str("a\u{A0}b")), [b"a\xC2\xA0b"]);
+
+        let mut string = Wtf8Buf::from_str("a ");
+        string.push(CodePoint::from_u32(0xDC00).unwrap());
+        string.push_str("b c");
+        assert_eq!(split(&string), [&b"a"[..], b"\xED\xB0\x80b", b"c"]);
+    }
+
+    #[test]
+    fn wtf8_cmp_str() {
+        use cmp::Ordering::*;
+
+        let mut string = Wtf8Buf::from_str("a\u{D7FF}");
+        assert!(string == "a\u{D7FF}");
+        assert!("a\u{D7FF}" == string);
+        assert!(*string == *"a\u{D7FF}");
+        assert!(*"a\u{D7FF}" == *string);
+        assert!(string != "a");
+        assert_eq!(string.partial_cmp("a"), Some(Greater));
+        assert_eq!("a\u{D7FF}".partial_cmp(&string), Some(Equal));
+
+        string.push(CodePoint::from_u32(0xD800).unwrap());
+        assert!(string != "a\u{D7FF}\u{FFFD}");
+        assert!(string > "a\u{D7FF}\u{D7FF}");
+        assert!(string < "a\u{D7FF}\u{E000}");
+        assert!(*"a\u{D7FF}\u{10000}" > *string);
+
+        // Joined surrogates compare equal to the supplementary code point.
+        string.push(CodePoint::from_u32(0xDC00).unwrap());
+        assert!(string == "a\u{D7FF}\u{10000}");
+        assert!(string[1..] == *"\u{D7FF}\u{10000}");
+    }
+
+    #[test]
+    fn wtf8_portable_bytes() {
+        assert_eq!(Wtf8::from_str("").to_portable_bytes(), b"\x01");
+        assert_eq!(Wtf8::from_str("aé 💩").to_portable_bytes(),
+                   b"\x01a\xC3\xA9 \xF0\x9F\x92\xA9");
+
+        let mut string = Wtf8Buf::from_str("a");
+        string.push(CodePoint::from_u32(0xD83D).unwrap());
+        string.push_str("b");
+        string.push(CodePoint::from_u32(0xDC00).unwrap());
+        string.push(CodePoint::from_u32(0xD800).unwrap());
+        let bytes = string.to_portable_bytes();
+        assert_eq!(bytes, b"\x01a\xFF\xD8\x3Db\xFF\xDC\x00\xFF\xD8\x00");
+        assert_eq!(Wtf8Buf::from_portable_bytes(&bytes).unwrap(), string);
+        assert_eq!(Wtf8Buf::from_portable_bytes(b"\x01").unwrap(), Wtf8Buf::new());
+        assert_eq!(Wtf8Buf::from_portable_bytes(b"\x01a\xC3\xA9").unwrap().bytes, b"a\xC3\xA9");
+
+        // Unknown versions.
+        assert!(Wtf8Buf::from_portable_bytes(b"").is_none());
+        assert!(Wtf8Buf::from_portable_bytes(b"\x02a").is_none());
+        // Invalid UTF-8, including the in-memory encoding of surrogates.
+        assert!(Wtf8Buf::from_portable_bytes(b"\x01\xC3").is_none());
+        assert!(Wtf8Buf::from_portable_bytes(b"\x01\xED\xA0\x80").is_none());
+        // Truncated or invalid escapes.
+        assert!(Wtf8Buf::from_portable_bytes(b"\x01\xFF\xD8").is_none());
+        assert!(Wtf8Buf::from_portable_bytes(b"\x01\xFF\x00\x61").is_none());
+        // Surrogate pairs are written as supplementary code points.
+        assert!(Wtf8Buf::from_portable_bytes(b"\x01\xFF\xD8\x3D\xFF\xDC\xA9").is_none());
+        assert!(Wtf8Buf::from_portable_bytes(b"\x01\xFF\xDC\xA9\xFF\xD8\x3D").is_some());
+    }
+
     #[test]
     fn wtf8_from_str() {
         assert_eq!(&Wtf8::from_str("").bytes, b"");
@@ -1181,6 +2446,34 @@ mod tests {
         &Wtf8::from_str("aé 💩")[5..];
     }
 
+    #[test]
+    fn wtf8_slice_inclusive() {
+        let slice = Wtf8::from_str("aé 💩");
+        assert_eq!(&slice[ops::RangeInclusive { start: 1, end: 3 }].bytes, b"\xC3\xA9 ");
+        assert_eq!(&slice[ops::RangeToInclusive { end: 2 }].bytes, b"a\xC3\xA9");
+    }
+
+    #[test]
+    #[should_panic]
+    fn wtf8_slice_inclusive_not_code_point_boundary() {
+        &Wtf8::from_str("aé 💩")[ops::RangeInclusive { start: 1, end: 1 }];
+    }
+
+    #[test]
+    fn wtf8_get() {
+        let slice = Wtf8::from_str("aé 💩");
+        assert_eq!(slice.get(1..4).map(|s| &s.bytes), Some(&b"\xC3\xA9 "[..]));
+        assert_eq!(slice.get(..).map(|s| &s.bytes), Some(&slice.bytes));
+        assert_eq!(slice.get(ops::RangeToInclusive { end: 2 }).map(|s| &s.bytes),
+                   Some(&b"a\xC3\xA9"[..]));
+        assert!(slice.get(2..4).is_none());
+        assert!(slice.get(4..1).is_none());
+        assert!(slice.get(5..).is_none());
+        assert!(slice.get(..9).is_none());
+        assert!(slice.get(ops::RangeInclusive { start: 0, end: usize::max_value() }).is_none());
+        assert_eq!(unsafe { &slice.get_unchecked(3..).bytes }, b" \xF0\x9F\x92\xA9");
+    }
+
     #[test]
     fn wtf8_ascii_byte_at() {
         let slice = Wtf8::from_str("aé 💩");
@@ -1205,6 +2498,41 @@ mod tests {
         assert_eq!(cp(&string), [Some('é'), Some(' '), Some('💩')]);
     }
 
+    #[test]
+    fn wtf8_chars_lossy() {
+        fn c(value: u32) -> CodePoint { CodePoint::from_u32(value).unwrap() }
+        let mut string = Wtf8Buf::from_str("é ");
+        string.push(c(0xD83D));
+        string.push_str("a");
+        string.push(c(0xDCA9));
+        string.push_str("💩");
+        assert_eq!(string.chars_lossy().collect::<String>(), string.to_string_lossy());
+        assert_eq!(string.chars_lossy().collect::<Vec<_>>(),
+                   ['é', ' ', '\u{FFFD}', 'a', '\u{FFFD}', '💩']);
+        assert_eq!(string.char_indices_lossy().collect::<Vec<_>>(),
+                   [(0, 'é'), (2, ' '), (3, '\u{FFFD}'), (6, 'a'), (7, '\u{FFFD}'), (10, '💩')]);
+
+        // A surrogate pair is a single supplementary char.
+        let mut string = Wtf8Buf::new();
+        string.push(c(0xD83D));
+        string.push(c(0xDCA9));
+        assert_eq!(string.char_indices_lossy().collect::<Vec<_>>(), [(0, '💩')]);
+        assert_eq!(Wtf8::from_str("").chars_lossy().next(), None);
+    }
+
+    #[test]
+    fn wtf8_last_code_point() {
+        fn c(value: u32) -> CodePoint { CodePoint::from_u32(value).unwrap() }
+        assert_eq!(Wtf8::from_str("").last_code_point(), None);
+        assert_eq!(Wtf8::from_str("a").last_code_point(), Some(c(0x61)));
+        assert_eq!(Wtf8::from_str("aé").last_code_point(), Some(c(0xE9)));
+        assert_eq!(Wtf8::from_str("aé 💩").last_code_point(), Some(c(0x1F4A9)));
+        let mut string = Wtf8Buf::from_str("aé");
+        string.push(c(0xD800));
+        assert_eq!(string.last_code_point(), Some(c(0xD800)));
+        assert_eq!(string.bytes, b"a\xC3\xA9\xED\xA0\x80");
+    }
+
     #[test]
     fn wtf8_as_str() {
         assert_eq!(Wtf8::from_str("").as_str(), Some(""));
@@ -1246,4 +2574,157 @@ mod tests {
         assert_eq!(string.encode_wide().collect::<Vec<_>>(),
                    vec![0x61, 0xE9, 0x20, 0xD83D, 0xD83D, 0xDCA9]);
     }
+
+    #[test]
+    fn wtf8_encode_wide_into() {
+        let mut string = Wtf8Buf::from_str("aé ");
+        string.push(CodePoint::from_u32(0xD83D).unwrap());
+        string.push_char('💩');
+        assert_eq!(string.encoded_wide_len(), 6);
+        assert_eq!(Wtf8Buf::new().encoded_wide_len(), 0);
+
+        let mut buf = vec![0x7A];
+        string.encode_wide_into(&mut buf);
+        assert_eq!(buf, vec![0x7A, 0x61, 0xE9, 0x20, 0xD83D, 0xD83D, 0xDCA9]);
+    }
+
+    #[test]
+    fn wtf8_code_point_windows() {
+        let mut string = Wtf8Buf::from_str("aé");
+        string.push(CodePoint::from_u32(0xD83D).unwrap());
+        string.push_char('💩');
+        let windows = |size| string.code_point_windows(size).map(|w| &w.bytes)
+                                                             .collect::<Vec<_>>();
+        assert_eq!(windows(1), [&b"a"[..], b"\xC3\xA9", b"\xED\xA0\xBD",
+                                b"\xF0\x9F\x92\xA9"]);
+        assert_eq!(windows(2), [&b"a\xC3\xA9"[..], b"\xC3\xA9\xED\xA0\xBD",
+                                b"\xED\xA0\xBD\xF0\x9F\x92\xA9"]);
+        assert_eq!(windows(4), [&string.bytes[..]]);
+        assert!(windows(5).is_empty());
+        assert_eq!(Wtf8::from_str("").code_point_windows(1).next(), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn wtf8_code_point_windows_zero_size() {
+        Wtf8::from_str("a").code_point_windows(0);
+    }
+
+    /// Applies random sequences of operations to a `Wtf8Buf` and to the
+    /// potentially ill-formed UTF-16 code units it's supposed to represent,
+    /// checking that the two agree after every step. Surrogates meeting at the
+    /// edges of pushed strings or slices must pair up exactly as they would
+    /// when concatenating the code units.
+    #[test]
+    fn wtf8buf_random_operations() {
+        use char::decode_utf16;
+        use collections::hash_map::DefaultHasher;
+        use hash::{Hash, Hasher};
+
+        // A small xorshift generator, so that failures are reproducible.
+        struct Rng(u32);
+        impl Rng {
+            fn below(&mut self, n: usize) -> usize {
+                self.0 ^= self.0 << 13;
+                self.0 ^= self.0 >> 17;
+                self.0 ^= self.0 << 5;
+                self.0 as usize % n
+            }
+        }
+
+        fn hash<T: Hash>(t: &T) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            t.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        fn code_points(units: &[u16]) -> Vec<u32> {
+            decode_utf16(units.iter().cloned()).map(|r| match r {
+                Ok(c) => c as u32,
+                Err(e) => e.unpaired_surrogate() as u32,
+            }).collect()
+        }
+
+        fn utf16_len(cp: u32) -> usize {
+            if cp >= 0x10000 { 2 } else { 1 }
+        }
+
+        fn wtf8_len(cp: u32) -> usize {
+            match cp {
+                0...0x7F => 1,
+                0x80...0x7FF => 2,
+                0x800...0xFFFF => 3,
+                _ => 4,
+            }
+        }
+
+        const CODE_POINTS: &[u32] = &[0x61, 0xE9, 0x20AC, 0x1F4A9, 0xD83D, 0xDCA9, 0xD800, 0xDFFF];
+        const STRS: &[&str] = &["", "a", "é€", "💩z"];
+        const UNITS: &[u16] = &[0x61, 0xE9, 0xD83D, 0xDCA9];
+
+        let mut rng = Rng(0x2545F491);
+        for _ in 0..50 {
+            let mut string = Wtf8Buf::new();
+            let mut model: Vec<u16> = Vec::new();
+
+            for _ in 0..40 {
+                match rng.below(5) {
+                    0 => {
+                        let cp = CODE_POINTS[rng.below(CODE_POINTS.len())];
+                        string.push(CodePoint::from_u32(cp).unwrap());
+                        match ::char::from_u32(cp) {
+                            Some(c) => model.extend_from_slice(c.encode_utf16(&mut [0; 2])),
+                            None => model.push(cp as u16),
+                        }
+                    }
+                    1 => {
+                        let s = STRS[rng.below(STRS.len())];
+                        string.push_str(s);
+                        model.extend(s.encode_utf16());
+                    }
+                    2 => {
+                        let units = (0..rng.below(4)).map(|_| UNITS[rng.below(UNITS.len())])
+                                                     .collect::<Vec<_>>();
+                        string.push_wtf8(&Wtf8Buf::from_wide(&units));
+                        model.extend(units);
+                    }
+                    3 => {
+                        let cps = code_points(&model);
+                        let n = rng.below(cps.len() + 1);
+                        string.truncate(cps[..n].iter().map(|&cp| wtf8_len(cp)).sum());
+                        let units = cps[..n].iter().map(|&cp| utf16_len(cp)).sum();
+                        model.truncate(units);
+                    }
+                    _ => {
+                        let last = code_points(&model).pop();
+                        assert_eq!(string.pop().map(|c| c.to_u32()), last);
+                        if let Some(cp) = last {
+                            let len = model.len() - utf16_len(cp);
+                            model.truncate(len);
+                        }
+                    }
+                }
+
+                assert_eq!(string.encode_wide().collect::<Vec<_>>(), model);
+                assert_eq!(string.encoded_wide_len(), model.len());
+                assert_eq!(string.code_points().map(|c| c.to_u32()).collect::<Vec<_>>(),
+                           code_points(&model));
+
+                let from_wide = Wtf8Buf::from_wide(&model);
+                assert_eq!(from_wide, string);
+                assert_eq!(hash(&from_wide), hash(&string));
+
+                // Splitting at any code point boundary and joining the halves
+                // back together is lossless.
+                let mut boundary = 0;
+                for cp in code_points(&model) {
+                    boundary += wtf8_len(cp);
+                    let mut joined = Wtf8Buf::new();
+                    joined.push_wtf8(&string[..boundary]);
+                    joined.push_wtf8(&string[boundary..]);
+                    assert_eq!(joined, string);
+                }
+            }
+        }
+    }
 }
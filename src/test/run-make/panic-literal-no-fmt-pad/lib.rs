@@ -0,0 +1,44 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![crate_type = "staticlib"]
+#![feature(lang_items)]
+#![no_std]
+
+use core::fmt::{self, Write};
+
+#[no_mangle]
+pub extern fn checked_div(a: u32, b: u32) -> u32 {
+    if b == 0 {
+        panic!("division by zero");
+    }
+    a / b
+}
+
+struct Sink;
+
+impl Write for Sink {
+    fn write_str(&mut self, _: &str) -> fmt::Result {
+        Ok(())
+    }
+}
+
+// The message is actually formatted so that the formatting machinery it needs
+// ends up in the library.
+#[lang = "panic_fmt"]
+#[no_mangle]
+pub extern fn rust_begin_panic(msg: fmt::Arguments, _file: &'static str, _line: u32,
+                               _col: u32) -> ! {
+    let _ = Sink.write_fmt(msg);
+    loop {}
+}
+
+#[lang = "eh_personality"]
+extern fn eh_personality() {}
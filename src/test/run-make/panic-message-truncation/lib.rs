@@ -0,0 +1,66 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![crate_type = "staticlib"]
+#![feature(lang_items)]
+#![no_std]
+
+use core::fmt::{self, Write};
+
+extern {
+    fn write(fd: i32, buf: *const u8, count: usize) -> isize;
+    fn exit(code: i32) -> !;
+}
+
+static mut WRITES: usize = 0;
+
+// Writes its digits over and over again, counting how often it was asked to.
+struct Digits;
+
+impl fmt::Display for Digits {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        loop {
+            unsafe { WRITES += 1; }
+            f.write_str("0123456789")?;
+        }
+    }
+}
+
+#[no_mangle]
+pub extern fn panic_with(which: i32) {
+    match which {
+        0 => panic!("short"),
+        1 => panic!("{}{}", "0123456789012345678901234567890123456789", "0123456789"),
+        // The 'é' would end at byte 33.
+        2 => panic!("{}é", "0123456789012345678901234567890"),
+        3 => panic!("{}", Digits),
+        _ => {}
+    }
+}
+
+struct Stdout;
+
+impl Write for Stdout {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        unsafe { write(1, s.as_ptr(), s.len()); }
+        Ok(())
+    }
+}
+
+#[lang = "panic_fmt"]
+#[no_mangle]
+pub extern fn rust_begin_panic(msg: fmt::Arguments, _file: &'static str, _line: u32,
+                               _col: u32) -> ! {
+    let _ = writeln!(Stdout, "{} ({} writes)", msg, unsafe { WRITES });
+    unsafe { exit(0) }
+}
+
+#[lang = "eh_personality"]
+extern fn eh_personality() {}
@@ -0,0 +1,9 @@
+// ignore-license
+#include <stdlib.h>
+
+void panic_with(int which);
+
+int main(int argc, char **argv) {
+    panic_with(atoi(argv[1]));
+    return 1;
+}
@@ -124,3 +124,8 @@ endif
 
 $(TMPDIR)/lib%.o: %.c
 	$(call COMPILE_OBJ,$@,$<)
+
+# Fails if the object file or archive $(1) defines or references a symbol
+# matching the pattern $(2). This can be used to guard optimizations that keep
+# pieces of libcore (e.g. the formatting machinery) out of small binaries.
+ASSERT_NO_SYMBOL = [ "$$(nm $(1) | grep -c '$(2)')" -eq "0" ]
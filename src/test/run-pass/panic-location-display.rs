@@ -0,0 +1,36 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![feature(panic_col)]
+
+// ignore-emscripten no threads support
+
+use std::panic;
+use std::sync::atomic::{AtomicBool, ATOMIC_BOOL_INIT, Ordering};
+use std::thread;
+
+static OK: AtomicBool = ATOMIC_BOOL_INIT;
+
+fn main() {
+    panic::set_hook(Box::new(|info| {
+        let location = info.location().unwrap();
+        let expected = format!("{}:{}:{}", location.file(), location.line(), location.column());
+        OK.store(location.to_string() == expected &&
+                 location.file() == file!() &&
+                 location.line() == 32,
+                 Ordering::SeqCst);
+    }));
+
+    let _ = thread::spawn(|| {
+        panic!("oops");
+    }).join();
+
+    assert!(OK.load(Ordering::SeqCst));
+}
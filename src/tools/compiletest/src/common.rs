@@ -132,6 +132,14 @@ pub struct Config {
     // Exactly match the filter, rather than a substring
     pub filter_exact: bool,
 
+    // Only run shard `k` (counting from 1) when splitting the tests into `n`
+    // shards, given as `(k, n)`
+    pub shard: Option<(usize, usize)>,
+
+    // Only run the tests which expect the compiler to emit the error with
+    // this code, e.g. `E0277`
+    pub error_code: Option<String>,
+
     // Write out a parseable log of tests that were run
     pub logfile: Option<PathBuf>,
 
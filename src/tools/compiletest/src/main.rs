@@ -26,8 +26,8 @@ extern crate diff;
 
 use std::env;
 use std::ffi::OsString;
-use std::fs;
-use std::io;
+use std::fs::{self, File};
+use std::io::{self, Read};
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use filetime::FileTime;
@@ -81,6 +81,10 @@ pub fn parse_config(args: Vec<String> ) -> Config {
                  run-pass-valgrind|pretty|debug-info|incremental|mir-opt)")
         .optflag("", "ignored", "run tests marked as ignored")
         .optflag("", "exact", "filters match exactly")
+        .optopt("", "test-shard", "split the tests into N shards and only run the Kth one \
+                                   (counting from 1)", "K/N")
+        .optopt("", "error-code", "only run the tests expecting the error with this code",
+                "CODE")
         .optopt("", "runtool", "supervisor program to run tests under \
                                 (eg. emulator, valgrind)", "PROGRAM")
         .optopt("", "host-rustcflags", "flags to pass to rustc for host", "FLAGS")
@@ -170,6 +174,8 @@ pub fn parse_config(args: Vec<String> ) -> Config {
         run_ignored: matches.opt_present("ignored"),
         filter: matches.free.first().cloned(),
         filter_exact: matches.opt_present("exact"),
+        shard: matches.opt_str("test-shard").map(|s| parse_shard(&s)),
+        error_code: matches.opt_str("error-code"),
         logfile: matches.opt_str("logfile").map(|s| PathBuf::from(&s)),
         runtool: matches.opt_str("runtool"),
         host_rustcflags: matches.opt_str("host-rustcflags"),
@@ -204,6 +210,14 @@ pub fn parse_config(args: Vec<String> ) -> Config {
     }
 }
 
+fn parse_shard(s: &str) -> (usize, usize) {
+    let mut parts = s.splitn(2, '/').map(|p| p.parse::<usize>().ok());
+    match (parts.next(), parts.next()) {
+        (Some(Some(k)), Some(Some(n))) if 1 <= k && k <= n => (k, n),
+        _ => panic!("argument for --test-shard must be K/N with 1 <= K <= N, but found `{}`", s),
+    }
+}
+
 pub fn log_config(config: &Config) {
     let c = config;
     logv(c, "configuration:".to_string());
@@ -221,6 +235,8 @@ pub fn log_config(config: &Config) {
                                    .as_ref()
                                    .map(|re| re.to_owned()))));
     logv(c, format!("filter_exact: {}", config.filter_exact));
+    logv(c, format!("shard: {:?}", config.shard));
+    logv(c, format!("error_code: {}", opt_str(&config.error_code)));
     logv(c, format!("runtool: {}", opt_str(&config.runtool)));
     logv(c, format!("host-rustcflags: {}",
                     opt_str(&config.host_rustcflags)));
@@ -358,6 +374,17 @@ pub fn make_tests(config: &Config) -> Vec<test::TestDescAndFn> {
                            &PathBuf::new(),
                            &mut tests)
         .unwrap();
+
+    if let Some((k, n)) = config.shard {
+        // The order tests are found in depends on the file system, so sort
+        // them first for all shards to agree on which tests belong where.
+        tests.sort_by_key(|t| t.desc.name.to_string());
+        tests = tests.into_iter()
+                     .enumerate()
+                     .filter(|&(i, _)| i % n == k - 1)
+                     .map(|(_, t)| t)
+                     .collect();
+    }
     tests
 }
 
@@ -381,7 +408,10 @@ fn collect_tests_from_dir(config: &Config,
                 base: base.to_path_buf(),
                 relative_dir: relative_dir_path.parent().unwrap().to_path_buf(),
             };
-            tests.push(make_test(config, &paths));
+            // run-make tests have no error annotations
+            if config.error_code.is_none() {
+                tests.push(make_test(config, &paths));
+            }
             return Ok(())
         }
     }
@@ -409,7 +439,9 @@ fn collect_tests_from_dir(config: &Config,
                 base: base.to_path_buf(),
                 relative_dir: relative_dir_path.to_path_buf(),
             };
-            tests.push(make_test(config, &paths))
+            if config.error_code.as_ref().map_or(true, |code| expects_error_code(&paths, code)) {
+                tests.push(make_test(config, &paths))
+            }
         } else if file_path.is_dir() {
             let relative_file_path = relative_dir_path.join(file.file_name());
             if &file_name == "auxiliary" {
@@ -435,6 +467,23 @@ fn collect_tests_from_dir(config: &Config,
     Ok(())
 }
 
+/// Returns whether the test expects the compiler to emit the error `code`,
+/// either in a `//~` annotation or, for UI tests, in the expected stderr. The
+/// code has to appear as a whole word, so `E0308` doesn't match `E03081`.
+fn expects_error_code(paths: &TestPaths, code: &str) -> bool {
+    let mentions = |path: &Path, annotations_only: bool| {
+        let mut contents = String::new();
+        if File::open(path).and_then(|mut f| f.read_to_string(&mut contents)).is_err() {
+            return false
+        }
+        contents.lines().any(|line| {
+            (!annotations_only || line.contains("//~")) &&
+                line.split(|c: char| !c.is_alphanumeric()).any(|word| word == code)
+        })
+    };
+    mentions(&paths.file, true) || mentions(&paths.file.with_extension("stderr"), false)
+}
+
 pub fn is_test(file_name: &OsString) -> bool {
     let file_name = file_name.to_str().unwrap();
 